@@ -0,0 +1,328 @@
+use std::{borrow::Borrow, fmt::Debug, mem::transmute};
+
+use thin_vec::ThinVec;
+
+use super::*;
+use crate::arena::*;
+
+/// Simulation state of multiple [`Brain`]s with the same topology (see [`Brain::same_topology`]).
+///
+/// All data is stored as structure-of-arrays: the values of a single neuron (or connection)
+/// for every lane are stored next to each other, so each of them is processed for all lanes
+/// in a single tight loop.
+/// The genes of every lane are copied into the batch, so changes to the brains are not picked up.
+pub struct BatchState<A, P, C>
+where
+    A: Activator,
+    P: 'static + Propagator,
+    C: Collector,
+{
+    lanes:             usize,
+    neuron_state:      Buffer<A>,
+    connection_state:  Buffer<P>,
+    activator_genes:   Buffer<A::Gene>,
+    propagator_genes:  Buffer<P::Gene>,
+    plan:              Plan,
+    modulation_buffer: ThinVec<P::Input<'static>>,
+    collectors:        Buffer<C>,
+}
+
+impl<A, P, C> Debug for BatchState<A, P, C>
+where
+    A: Activator,
+    P: 'static + Propagator,
+    C: Collector,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("BatchState")
+            .field("lanes", &self.lanes)
+            .field("neuron_state", &self.neuron_state)
+            .field("connection_state", &self.connection_state)
+            .field("plan", &self.plan)
+            .field("collectors", &self.collectors)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, P, C> BatchState<A, P, C>
+where
+    //  P -> C -> A -> P
+    //      /      \
+    //  in /        \ out
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    A: 'static + for<'a> Activator<Input<'a> = C::Output<'a>, Output<'a> = P::Input<'a>>,
+    P: 'static + for<'p> Propagator<Output<'p> = C::Input<'p>>,
+    C: Collector,
+{
+    /// Creates a lane for each of `brains`, all of them using the interface neurons of `body`.
+    /// Returns `None` when `brains` is empty or the brains do not share the same topology.
+    /// # Safety
+    /// Same as [`State::create_for`].
+    pub unsafe fn create_for<X: Phenotype>(
+        brains: &[&Brain<A, P>],
+        body: &Body<X>,
+        arena: &mut Arena,
+    ) -> Option<Self> {
+        let (first, rest) = brains.split_first()?;
+        if !rest.iter().all(|brain| first.same_topology(brain)) {
+            return None;
+        }
+        let lanes = brains.len();
+        let neurons = first.neurons().len() * lanes;
+        let connections = first.connections().len() * lanes;
+        // SAFETY: guarantied by caller
+        Some(unsafe {
+            Self {
+                lanes,
+                neuron_state: arena.alloc_slice_with(neurons, A::default),
+                connection_state: arena.alloc_slice_with(connections, P::default),
+                activator_genes: arena.alloc_slice_from_iter((0..neurons).map(|i| {
+                    brains[i % lanes].neurons()[i / lanes].activator_gene.clone()
+                })),
+                propagator_genes: arena.alloc_slice_from_iter((0..connections).map(|i| {
+                    brains[i % lanes].connections()[i / lanes].propagator_gene.clone()
+                })),
+                plan: Plan::compile(first, body),
+                modulation_buffer: ThinVec::new(),
+                collectors: arena.alloc_slice_with(neurons, C::default),
+            }
+        })
+    }
+
+    /// Number of brains simulated in parallel.
+    pub fn lanes(&self) -> usize {
+        self.lanes
+    }
+
+    /// Moves all buffers (including the genes of every lane) into `arena`,
+    /// see [`State::move_buffers`].
+    /// # Safety
+    /// Same as [`State::create_for`], the state now belongs to `arena`.
+    pub unsafe fn move_buffers(&mut self, arena: &mut Arena) {
+        // SAFETY: guarantied by caller
+        unsafe {
+            arena.move_into(&mut self.neuron_state);
+            arena.move_into(&mut self.connection_state);
+            arena.move_into(&mut self.activator_genes);
+            arena.move_into(&mut self.propagator_genes);
+            arena.move_into(&mut self.collectors);
+        }
+    }
+
+    /// Returns the memory of all buffers to `arena`, so it can be reused by other states.
+    /// # Safety
+    /// Assumes that the state was created in `arena` (or its buffers were moved into it).
+    pub unsafe fn free_buffers(self, arena: &mut Arena) {
+        // SAFETY: guarantied by caller
+        unsafe {
+            arena.free(self.neuron_state);
+            arena.free(self.connection_state);
+            arena.free(self.activator_genes);
+            arena.free(self.propagator_genes);
+            arena.free(self.collectors);
+        }
+    }
+
+    #[inline(always)]
+    fn push(
+        source: &A,
+        edge: (&P::Gene, &mut P),
+        collector: &mut C,
+        (neurons, order): (&[A], &DenseOrder),
+        (lane, lanes): (usize, usize),
+        buffer: &mut ThinVec<P::Input<'_>>,
+        config: &Config<A, P, C>,
+    ) {
+        let input = source.output();
+        // SAFETY: `buffer` is only populated here and cleared before returning (see `State::push`)
+        let buffer =
+            unsafe { transmute::<&mut ThinVec<P::Input<'_>>, &mut ThinVec<P::Input<'_>>>(buffer) };
+        buffer.extend(
+            edge.1
+                .modulation(edge.0, &config.propagator)
+                .map(|id| neurons[order.index(*id.borrow()) * lanes + lane].output()),
+        );
+        let signal = edge.1.propagate(input, buffer, edge.0, &config.propagator);
+        collector.push(signal, &config.collector);
+        // NOTE: this is required by the assumption above
+        buffer.clear();
+    }
+
+    /// Advances the simulation of all lanes by a single step, see [`State::step`].
+    ///
+    /// `inputs` and `outputs` are grouped by interface neuron, so the value of lane `l`
+    /// for the `i`-th sensor is `inputs[i * lanes + l]` (the same goes for actions and `outputs`).
+    pub fn step<I, O>(&mut self, inputs: &[I], outputs: &mut [O], config: &Config<A, P, C>)
+    where
+        for<'c> &'c I: Into<C::Input<'c>>,
+        O: for<'a> From<A::Output<'a>>,
+    {
+        let lanes = self.lanes;
+        let mut inputs = inputs.chunks_exact(lanes);
+        let mut outputs = outputs.chunks_exact_mut(lanes);
+        for instruction in self.plan.instructions() {
+            match *instruction {
+                Instruction::Input(index) => {
+                    let inputs = inputs.next().expect("input buffer is not big enough");
+                    let collectors = &mut self.collectors[index * lanes..][..lanes];
+                    for (collector, input) in collectors.iter_mut().zip(inputs) {
+                        collector.push(input.into(), &config.collector);
+                    }
+                },
+                Instruction::Propagate { connection, from, to } => {
+                    let sources = &self.neuron_state[from * lanes..][..lanes];
+                    let states = &mut self.connection_state[connection * lanes..][..lanes];
+                    let genes = &self.propagator_genes[connection * lanes..][..lanes];
+                    let collectors = &mut self.collectors[to * lanes..][..lanes];
+                    let edges = genes.iter().zip(states);
+                    let lane_iter = sources.iter().zip(edges).zip(collectors.iter_mut());
+                    for (lane, ((source, edge), collector)) in lane_iter.enumerate() {
+                        Self::push(
+                            source,
+                            edge,
+                            collector,
+                            (&self.neuron_state, self.plan.order()),
+                            (lane, lanes),
+                            &mut self.modulation_buffer,
+                            config,
+                        );
+                    }
+                },
+                Instruction::Activate(index) => {
+                    let states = &mut self.neuron_state[index * lanes..][..lanes];
+                    let genes = &self.activator_genes[index * lanes..][..lanes];
+                    let collectors = &mut self.collectors[index * lanes..][..lanes];
+                    let lane_iter = states.iter_mut().zip(genes).zip(collectors.iter_mut());
+                    for ((state, gene), collector) in lane_iter {
+                        let input = collector.collect(&config.collector);
+                        state.activate(input, gene, &config.activator);
+                        collector.clear(&config.collector);
+                    }
+                },
+                Instruction::Output(index) => {
+                    let outputs = outputs.next().expect("output buffer is not big enough");
+                    let states = &self.neuron_state[index * lanes..][..lanes];
+                    for (output, state) in outputs.iter_mut().zip(states) {
+                        *output = state.output().into();
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::*;
+
+    #[derive(Debug, Clone)]
+    struct Weight {
+        value:     f64,
+        modulator: Option<NeuronID>,
+    }
+    #[derive(Debug, Default)]
+    struct Weighted;
+    impl Propagator for Weighted {
+        type Config = ();
+        type Gene = Weight;
+        type Input<'i>
+            = f64
+        where
+            Self: 'i;
+        type Output<'o>
+            = f64
+        where
+            Self: 'o;
+
+        fn modulation(
+            &self,
+            gene: &Self::Gene,
+            _config: &Self::Config,
+        ) -> impl Iterator<Item: Borrow<NeuronID>> {
+            gene.modulator.into_iter()
+        }
+
+        fn referenced_neurons(gene: &Self::Gene) -> impl Iterator<Item: Borrow<NeuronID>> {
+            gene.modulator.into_iter()
+        }
+
+        fn propagate(
+            &mut self,
+            input: Self::Input<'_>,
+            modulation: &[Self::Input<'_>],
+            gene: &Self::Gene,
+            _config: &Self::Config,
+        ) -> Self::Output<'_> {
+            input * gene.value * modulation.iter().product::<f64>()
+        }
+    }
+    type TestBrain = Brain<Value, Weighted>;
+    type TestBody = Body<TestPhenotype>;
+    type TestConfig = Config<Value, Weighted, Sum>;
+
+    /// inputs: <0>, <1>
+    /// <2> = a * <0> + b * <1> * <0> + c * previous <2>
+    /// outputs: <2>
+    fn network([a, b, c]: [f64; 3]) -> (TestBrain, TestBody) {
+        let mut brain = TestBrain::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        let weight = |value, modulator| Weight { value, modulator };
+        brain.add_connection(ids[0], ids[2], weight(a, None)).unwrap();
+        brain.add_connection(ids[1], ids[2], weight(b, Some(ids[0]))).unwrap();
+        brain.add_recurrent_connection(ids[2], ids[2], weight(c, None)).unwrap();
+        // SAFETY: sensors and actions are listed in order
+        let body = unsafe {
+            TestBody::new_unchecked(
+                ids[..2].iter().map(|&neuron| Sensor { neuron, gene: () }).collect(),
+                ids[2..].iter().map(|&neuron| Action { neuron, gene: () }).collect(),
+                TestPhenotype,
+            )
+        };
+        (brain, body)
+    }
+
+    #[test]
+    fn batch_matches_single_states() {
+        let weights = [[1.0, 0.0, 0.0], [0.5, 2.0, 0.5], [-1.0, 1.0, 1.0], [0.0, -3.0, 0.25]];
+        let networks = weights.map(network);
+        let brains = networks.iter().map(|(brain, _)| brain).collect::<Vec<_>>();
+        let body = &networks[0].1;
+        let config = TestConfig::default();
+        let mut arena = Arena::new();
+        let mut batch = unsafe { BatchState::<_, _, Sum>::create_for(&brains, body, &mut arena) }
+            .expect("same topology");
+        let mut states = brains
+            .iter()
+            .map(|brain| unsafe { State::<_, _, Sum>::create_for(brain, body, &mut arena) })
+            .collect::<Vec<_>>();
+        assert_eq!(weights.len(), batch.lanes());
+        for inputs in [[1.0, 2.0], [0.5, -1.0], [-2.0, 0.0]] {
+            let batch_inputs = inputs
+                .iter()
+                .flat_map(|input| brains.iter().map(|_| Reading(*input)))
+                .collect::<Vec<_>>();
+            let mut batch_outputs = vec![0.0; brains.len()];
+            batch.step(&batch_inputs, &mut batch_outputs, &config);
+            for (lane, (state, brain)) in states.iter_mut().zip(&brains).enumerate() {
+                let mut outputs = [0.0];
+                state.step(brain, &inputs.map(Reading), &mut outputs, &config);
+                assert_eq!(outputs[0], batch_outputs[lane], "lane {lane}");
+            }
+        }
+    }
+
+    #[test]
+    fn different_topologies_are_rejected() {
+        let (brain, body) = network([1.0, 1.0, 1.0]);
+        let mut other = brain.clone();
+        other.add_neuron(());
+        let mut arena = Arena::new();
+        let mut create = |brains: &[&TestBrain]| {
+            unsafe { BatchState::<_, _, Sum>::create_for(brains, &body, &mut arena) }.is_some()
+        };
+        assert!(!create(&[&brain, &other]));
+        assert!(!create(&[]));
+        assert!(create(&[&brain, &brain]));
+    }
+}
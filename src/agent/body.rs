@@ -1,30 +1,112 @@
-use std::fmt::Debug;
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt::{Debug, Display},
+};
 
+use rand::Rng;
 use thin_vec::ThinVec;
 
-use super::NeuronID;
+use super::{Activator, Brain, NeuronID, NeuronOrder, Propagator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyError {
+    /// The [`NeuronID`] of a sensor or action is not part of the [`NeuronOrder`].
+    MissingNeuron(NeuronID),
+    /// The sensor or action using this [`NeuronID`] is not sorted using [`NeuronOrder::cmp`].
+    Unsorted(NeuronID),
+    /// The [`NeuronID`] is used by more than one sensor or more than one action.
+    DuplicateNeuron(NeuronID),
+}
+impl Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingNeuron(id) => write!(f, "neuron {id} is not part of the order"),
+            Self::Unsorted(id) => write!(f, "interface neuron {id} is out of order"),
+            Self::DuplicateNeuron(id) => write!(f, "interface neuron {id} is used more than once"),
+        }
+    }
+}
+impl Error for BodyError {}
+
+/// Location of an interface neuron in a geometric substrate, used by indirect encodings.
+pub type Position = [f64; 2];
 
 // TODO: add config
-pub trait Phenotype {
+pub trait Phenotype: Debug + Clone {
     type SensorGene: Debug + Clone;
     type ActionGene: Debug + Clone;
-    // TODO: functions to use in Genome.populate for dealing with Body mutations
-    // make sure sensors and actions are sorted using `NeuronOrder::cmp`
+
+    /// Returns a measure of how different two phenotypes are, meant to be used by [`Genome::distance`].
+    ///
+    /// [`Genome::distance`]: super::Genome::distance
+    #[expect(unused_variables)]
+    fn distance(&self, other: &Self) -> f64 {
+        0.0
+    }
+    /// Returns the [`Position`] of a sensor, genomes pick a default layout when this is `None`.
+    #[expect(unused_variables)]
+    fn sensor_position(&self, gene: &Self::SensorGene) -> Option<Position> {
+        None
+    }
+    /// Returns the [`Position`] of an action, genomes pick a default layout when this is `None`.
+    #[expect(unused_variables)]
+    fn action_position(&self, gene: &Self::ActionGene) -> Option<Position> {
+        None
+    }
+    /// Creates the gene of a new sensor, genomes only add sensors when this returns `Some`.
+    #[expect(unused_variables)]
+    fn create_sensor(&self, rng: &mut impl Rng) -> Option<Self::SensorGene> {
+        None
+    }
+    /// Creates the gene of a new action, genomes only add actions when this returns `Some`.
+    #[expect(unused_variables)]
+    fn create_action(&self, rng: &mut impl Rng) -> Option<Self::ActionGene> {
+        None
+    }
+    /// Decides if a sensor can be removed by a mutation.
+    #[expect(unused_variables)]
+    fn can_remove_sensor(&self, gene: &Self::SensorGene) -> bool {
+        false
+    }
+    /// Decides if an action can be removed by a mutation.
+    #[expect(unused_variables)]
+    fn can_remove_action(&self, gene: &Self::ActionGene) -> bool {
+        false
+    }
+    /// Changes the gene of an existing sensor.
+    #[expect(unused_variables)]
+    fn mutate_sensor(&self, gene: &mut Self::SensorGene, rng: &mut impl Rng) {}
+    /// Changes the gene of an existing action.
+    #[expect(unused_variables)]
+    fn mutate_action(&self, gene: &mut Self::ActionGene, rng: &mut impl Rng) {}
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sensor<S> {
     pub neuron: NeuronID,
     pub gene:   S,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Action<A> {
     pub neuron: NeuronID,
     pub gene:   A,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P: serde::Serialize, P::SensorGene: serde::Serialize, \
+                     P::ActionGene: serde::Serialize",
+        deserialize = "P: serde::Deserialize<'de>, P::SensorGene: serde::Deserialize<'de>, \
+                       P::ActionGene: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Body<P: Phenotype> {
     sensors:   ThinVec<Sensor<P::SensorGene>>,
     actions:   ThinVec<Action<P::ActionGene>>,
@@ -34,6 +116,133 @@ impl<P> Body<P>
 where
     P: Phenotype,
 {
+    /// Starts building a [`Body`] that is checked against a [`NeuronOrder`] when it is finished.
+    pub fn builder(phenotype: P) -> BodyBuilder<P> {
+        BodyBuilder::new(phenotype)
+    }
+
+    /// Creates an instance of [`Body`] from existing data.
+    /// # Safety
+    /// Assumes that sensors and actions are sorted using [`NeuronOrder::cmp`] of the matching [`Brain`].
+    ///
+    /// [`NeuronOrder::cmp`]: super::NeuronOrder::cmp
+    /// [`Brain`]: super::Brain
+    pub unsafe fn new_unchecked(
+        sensors: ThinVec<Sensor<P::SensorGene>>,
+        actions: ThinVec<Action<P::ActionGene>>,
+        phenotype: P,
+    ) -> Self {
+        Self { sensors, actions, phenotype }
+    }
+
+    /// Checks that all sensors and actions are part of `order` and sorted using [`NeuronOrder::cmp`].
+    pub fn validate(&self, order: &NeuronOrder) -> Result<(), BodyError> {
+        let check = |neurons: &mut dyn Iterator<Item = NeuronID>| {
+            let mut previous = None;
+            for id in neurons {
+                order.index(id).ok_or(BodyError::MissingNeuron(id))?;
+                match previous.and_then(|previous| order.cmp(previous, id)) {
+                    Some(Ordering::Equal) => return Err(BodyError::DuplicateNeuron(id)),
+                    Some(Ordering::Greater) => return Err(BodyError::Unsorted(id)),
+                    _ => previous = Some(id),
+                }
+            }
+            Ok(())
+        };
+        check(&mut self.iter_sensor_neurons())?;
+        check(&mut self.iter_action_neurons())
+    }
+
+    /// Restores the order of sensors and actions after neurons of the matching [`Brain`] moved.
+    pub fn sort(&mut self, order: &NeuronOrder) -> Result<(), BodyError> {
+        let index = |id| order.index(id).ok_or(BodyError::MissingNeuron(id));
+        for sensor in &self.sensors {
+            index(sensor.neuron)?;
+        }
+        for action in &self.actions {
+            index(action.neuron)?;
+        }
+        self.sensors.sort_by_cached_key(|sensor| order.index(sensor.neuron));
+        self.actions.sort_by_cached_key(|action| order.index(action.neuron));
+        Ok(())
+    }
+
+    /// Adds a new sensor backed by a new unconnected [`Neuron`] in `brain`.
+    /// All sensors are sorted again, so neurons of `brain` may have moved since the last call.
+    /// Returns the [`NeuronID`] of the new neuron.
+    ///
+    /// [`Neuron`]: super::Neuron
+    pub fn add_sensor<A: Activator, Q: Propagator>(
+        &mut self,
+        brain: &mut Brain<A, Q>,
+        gene: P::SensorGene,
+        activator_gene: A::Gene,
+    ) -> NeuronID {
+        let neuron = brain.add_neuron(activator_gene);
+        self.sensors.push(Sensor { neuron, gene });
+        self.sensors.sort_by_cached_key(|sensor| brain.order().index(sensor.neuron));
+        neuron
+    }
+
+    /// Adds a new action backed by a new unconnected [`Neuron`] in `brain`.
+    /// All actions are sorted again, so neurons of `brain` may have moved since the last call.
+    /// Returns the [`NeuronID`] of the new neuron.
+    ///
+    /// [`Neuron`]: super::Neuron
+    pub fn add_action<A: Activator, Q: Propagator>(
+        &mut self,
+        brain: &mut Brain<A, Q>,
+        gene: P::ActionGene,
+        activator_gene: A::Gene,
+    ) -> NeuronID {
+        let neuron = brain.add_neuron(activator_gene);
+        self.actions.push(Action { neuron, gene });
+        self.actions.sort_by_cached_key(|action| brain.order().index(action.neuron));
+        neuron
+    }
+
+    /// Removes a sensor together with its [`Neuron`] and all connections using it from `brain`.
+    ///
+    /// [`Neuron`]: super::Neuron
+    pub fn remove_sensor<A: Activator, Q: Propagator>(
+        &mut self,
+        brain: &mut Brain<A, Q>,
+        neuron: NeuronID,
+    ) -> Option<P::SensorGene> {
+        let index = self.sensors.iter().position(|sensor| sensor.neuron == neuron)?;
+        brain.remove_neuron(neuron);
+        // NOTE: removing a neuron keeps the relative order of all other neurons
+        Some(self.sensors.remove(index).gene)
+    }
+
+    /// Removes an action together with its [`Neuron`] and all connections using it from `brain`.
+    ///
+    /// [`Neuron`]: super::Neuron
+    pub fn remove_action<A: Activator, Q: Propagator>(
+        &mut self,
+        brain: &mut Brain<A, Q>,
+        neuron: NeuronID,
+    ) -> Option<P::ActionGene> {
+        let index = self.actions.iter().position(|action| action.neuron == neuron)?;
+        brain.remove_neuron(neuron);
+        // NOTE: removing a neuron keeps the relative order of all other neurons
+        Some(self.actions.remove(index).gene)
+    }
+
+    /// Lets the [`Phenotype`] change every sensor and action gene with a chance of `chance`.
+    pub fn mutate_genes(&mut self, rng: &mut impl Rng, chance: f64) {
+        for sensor in &mut self.sensors {
+            if rng.random_bool(chance) {
+                self.phenotype.mutate_sensor(&mut sensor.gene, rng);
+            }
+        }
+        for action in &mut self.actions {
+            if rng.random_bool(chance) {
+                self.phenotype.mutate_action(&mut action.gene, rng);
+            }
+        }
+    }
+
     pub fn iter_sensor_neurons(&self) -> impl Iterator<Item = NeuronID> {
         self.sensors.iter().map(|sensor| sensor.neuron)
     }
@@ -62,3 +271,118 @@ where
         &self.phenotype
     }
 }
+
+/// Collects sensors and actions in any order, see [`Body::builder`].
+#[derive(Debug, Clone)]
+pub struct BodyBuilder<P: Phenotype> {
+    sensors:   ThinVec<Sensor<P::SensorGene>>,
+    actions:   ThinVec<Action<P::ActionGene>>,
+    phenotype: P,
+}
+impl<P> BodyBuilder<P>
+where
+    P: Phenotype,
+{
+    pub fn new(phenotype: P) -> Self {
+        Self { sensors: ThinVec::new(), actions: ThinVec::new(), phenotype }
+    }
+
+    pub fn sensor(mut self, neuron: NeuronID, gene: P::SensorGene) -> Self {
+        self.sensors.push(Sensor { neuron, gene });
+        self
+    }
+
+    pub fn action(mut self, neuron: NeuronID, gene: P::ActionGene) -> Self {
+        self.actions.push(Action { neuron, gene });
+        self
+    }
+
+    pub fn sensors(mut self, sensors: impl IntoIterator<Item = Sensor<P::SensorGene>>) -> Self {
+        self.sensors.extend(sensors);
+        self
+    }
+
+    pub fn actions(mut self, actions: impl IntoIterator<Item = Action<P::ActionGene>>) -> Self {
+        self.actions.extend(actions);
+        self
+    }
+
+    /// Sorts all sensors and actions using [`NeuronOrder::cmp`] of the matching [`Brain`].
+    /// Fails when a [`NeuronID`] is not part of `order` or used by more than one sensor or action.
+    pub fn build(self, order: &NeuronOrder) -> Result<Body<P>, BodyError> {
+        let Self { sensors, actions, phenotype } = self;
+        let mut body = Body { sensors, actions, phenotype };
+        body.sort(order)?;
+        body.validate(order)?;
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{Identity, Value};
+
+    #[derive(Debug, Clone)]
+    struct TestPhenotype;
+    impl Phenotype for TestPhenotype {
+        type ActionGene = char;
+        type SensorGene = char;
+    }
+
+    fn order(count: usize) -> (NeuronOrder, Vec<NeuronID>) {
+        let mut order = NeuronOrder::new();
+        let mut ids = Vec::new();
+        for i in 0..count {
+            let id = order.next_free(ids.last().copied()).unwrap();
+            // SAFETY: every neuron gets a different index
+            unsafe { order.set_unchecked(id, Some(count - 1 - i)) };
+            ids.push(id);
+        }
+        (order, ids)
+    }
+
+    #[test]
+    fn builder_sorts_interface() {
+        let (order, ids) = order(4);
+        let body = Body::builder(TestPhenotype)
+            .sensor(ids[1], 'a')
+            .sensor(ids[3], 'b')
+            .action(ids[0], 'c')
+            .action(ids[2], 'd')
+            .build(&order)
+            .unwrap();
+        assert_eq!(vec![ids[3], ids[1]], body.iter_sensor_neurons().collect::<Vec<_>>());
+        assert_eq!(vec!['b', 'a'], body.iter_sensors().copied().collect::<Vec<_>>());
+        assert_eq!(vec![ids[2], ids[0]], body.iter_action_neurons().collect::<Vec<_>>());
+        assert_eq!(vec!['d', 'c'], body.iter_actions().copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn builder_rejects_invalid_interface() {
+        let (order, ids) = order(2);
+        let other = order.next_free(ids.last().copied()).unwrap();
+        let missing = Body::builder(TestPhenotype).sensor(other, 'a').build(&order);
+        assert_eq!(BodyError::MissingNeuron(other), missing.unwrap_err());
+        let duplicate = Body::builder(TestPhenotype)
+            .action(ids[0], 'a')
+            .action(ids[0], 'b')
+            .build(&order);
+        assert_eq!(BodyError::DuplicateNeuron(ids[0]), duplicate.unwrap_err());
+        let shared = Body::builder(TestPhenotype).sensor(ids[1], 'a').action(ids[1], 'b');
+        assert!(shared.build(&order).is_ok());
+    }
+
+    #[test]
+    fn add_sensor_follows_moved_neurons() {
+        let mut brain = Brain::<Value, Identity>::new();
+        let mut body = Body::builder(TestPhenotype).build(brain.order()).unwrap();
+        let a = body.add_sensor(&mut brain, 'a', ());
+        let b = body.add_sensor(&mut brain, 'b', ());
+        // moves `a` behind `b`
+        brain.add_connection(b, a, ()).unwrap();
+        let c = body.add_sensor(&mut brain, 'c', ());
+        assert!(body.validate(brain.order()).is_ok());
+        assert_eq!(vec![b, a, c], body.iter_sensor_neurons().collect::<Vec<_>>());
+    }
+}
@@ -1,11 +1,98 @@
-use std::{collections::VecDeque, fmt::Debug};
+use std::{
+    borrow::Borrow,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    error::Error,
+    fmt::{Debug, Display},
+    hash::{DefaultHasher, Hash, Hasher},
+};
 
 use bit_set::BitSet;
 use thin_vec::ThinVec;
 
 use super::*;
 
-#[derive(Clone, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrainError {
+    /// The [`NeuronID`] is not part of the [`NeuronOrder`].
+    MissingNeuron(NeuronID),
+    /// There is no [`Connection`] between the two neurons.
+    MissingConnection { from: NeuronID, to: NeuronID },
+    /// `to` would have to be evaluated before `from` which is already depending on `to`.
+    Cycle { from: NeuronID, to: NeuronID },
+    /// The [`NeuronOrder`] does not point to the position of the [`Neuron`] in the list.
+    InvalidOrder(NeuronID),
+    /// More than one [`Neuron`] uses this [`NeuronID`].
+    DuplicateNeuron(NeuronID),
+    /// The [`Connection`] at index `connection` references a neuron that does not exist.
+    DanglingConnection { connection: usize, neuron: NeuronID },
+    /// The [`Connection`] at this index is not sorted by the index of its target.
+    UnsortedConnection(usize),
+    /// `before` has to be evaluated before `after` but is placed behind it.
+    OutOfOrder { before: NeuronID, after: NeuronID },
+    /// The modulation input `modulator` of the [`Connection`] at index `connection`
+    /// is placed behind its target.
+    ModulationOutOfOrder { connection: usize, modulator: NeuronID },
+}
+impl Display for BrainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingNeuron(id) => write!(f, "neuron {id} is not part of the order"),
+            Self::MissingConnection { from, to } =>
+                write!(f, "there is no connection {from} -> {to}"),
+            Self::Cycle { from, to } => write!(f, "dependency {from} -> {to} would close a cycle"),
+            Self::InvalidOrder(id) => write!(f, "order of neuron {id} does not match its position"),
+            Self::DuplicateNeuron(id) => write!(f, "neuron {id} is listed more than once"),
+            Self::DanglingConnection { connection, neuron } =>
+                write!(f, "connection {connection} references missing neuron {neuron}"),
+            Self::UnsortedConnection(index) =>
+                write!(f, "connection {index} is not sorted by its target"),
+            Self::OutOfOrder { before, after } =>
+                write!(f, "neuron {before} has to be evaluated before {after}"),
+            Self::ModulationOutOfOrder { connection, modulator } => write!(
+                f,
+                "modulator {modulator} of connection {connection} has to be evaluated before its \
+                 target"
+            ),
+        }
+    }
+}
+impl Error for BrainError {}
+
+/// Everything removed by [`Brain::prune`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Hidden neurons that are not influenced by any sensor.
+    pub unreachable: ThinVec<NeuronID>,
+    /// Hidden neurons that don't influence any action.
+    pub dead_ends:   ThinVec<NeuronID>,
+    /// Connections `(from, to)` without any effect, see [`Propagator::is_silent`].
+    pub silent:      ThinVec<(NeuronID, NeuronID)>,
+    /// Connections `(from, to)` that were removed together with a neuron they use.
+    pub detached:    ThinVec<(NeuronID, NeuronID)>,
+}
+impl PruneReport {
+    /// Checks if nothing was removed.
+    pub fn is_empty(&self) -> bool {
+        self.unreachable.is_empty()
+            && self.dead_ends.is_empty()
+            && self.silent.is_empty()
+            && self.detached.is_empty()
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(
+            serialize = "A::Gene: serde::Serialize, P::Gene: serde::Serialize",
+            deserialize = "A::Gene: serde::Deserialize<'de>, P::Gene: serde::Deserialize<'de>"
+        ),
+        try_from = "UncheckedBrain<A, P>"
+    )
+)]
 pub struct Brain<A, P>
 where
     A: Activator,
@@ -14,6 +101,49 @@ where
     neurons:     ThinVec<Neuron<A>>,
     connections: ThinVec<Connection<P>>,
     order:       NeuronOrder,
+    /// See [`Brain::fingerprint`], recomputed after every change of the topology.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    fingerprint: u64,
+}
+
+/// Deserialized [`Brain`] before its invariants are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(deserialize = "A::Gene: serde::Deserialize<'de>, P::Gene: serde::Deserialize<'de>"))]
+struct UncheckedBrain<A, P>
+where
+    A: Activator,
+    P: Propagator,
+{
+    neurons:     ThinVec<Neuron<A>>,
+    connections: ThinVec<Connection<P>>,
+    order:       NeuronOrder,
+}
+#[cfg(feature = "serde")]
+impl<A, P> TryFrom<UncheckedBrain<A, P>> for Brain<A, P>
+where
+    A: Activator,
+    P: Propagator,
+{
+    type Error = BrainError;
+
+    fn try_from(value: UncheckedBrain<A, P>) -> Result<Self, Self::Error> {
+        Self::try_new(value.neurons, value.connections, value.order)
+    }
+}
+impl<A, P> Clone for Brain<A, P>
+where
+    A: Activator,
+    P: Propagator,
+{
+    fn clone(&self) -> Self {
+        Self {
+            neurons:     self.neurons.clone(),
+            connections: self.connections.clone(),
+            order:       self.order.clone(),
+            fingerprint: self.fingerprint,
+        }
+    }
 }
 impl<A, P> Default for Brain<A, P>
 where
@@ -25,6 +155,7 @@ where
             neurons:     ThinVec::new(),
             connections: ThinVec::new(),
             order:       NeuronOrder::new(),
+            fingerprint: fingerprint::<A, P>(&[], &[]),
         }
     }
 }
@@ -35,15 +166,28 @@ where
 {
     /// Creats an instance of [`Brain`] from existing data.
     /// # Safety
-    /// Assumes that neurons are sorted in topological order.
-    /// Assumes that connections are sorted by `Connection.to` using `Neuron.id` as a sort index.
+    /// Assumes that neurons are sorted in topological order respecting [`Connection::dependencies`].
+    /// Assumes that connections are sorted by `Connection.to` using the index in `order` as a sort index.
     /// Asummes that inputs and outputs are sorted using `Neuron.id` as a sort index.
     pub unsafe fn new_unchecked(
         neurons: ThinVec<Neuron<A>>,
         connections: ThinVec<Connection<P>>,
         order: NeuronOrder,
     ) -> Self {
-        Self { neurons, connections, order }
+        let fingerprint = fingerprint(&neurons, &connections);
+        Self { neurons, connections, order, fingerprint }
+    }
+
+    /// Creates an instance of [`Brain`] from existing data, see [`Brain::validate`].
+    pub fn try_new(
+        neurons: ThinVec<Neuron<A>>,
+        connections: ThinVec<Connection<P>>,
+        order: NeuronOrder,
+    ) -> Result<Self, BrainError> {
+        let fingerprint = fingerprint(&neurons, &connections);
+        let brain = Self { neurons, connections, order, fingerprint };
+        brain.validate()?;
+        Ok(brain)
     }
 
     /// Creates an empty instance of [`Brain`]
@@ -63,6 +207,23 @@ where
         &self.order
     }
 
+    /// Checks if both brains contain the same neurons and connections in the same order,
+    /// ignoring all genes.
+    pub fn same_topology(&self, other: &Self) -> bool {
+        self.neurons.len() == other.neurons.len()
+            && self.connections.len() == other.connections.len()
+            && self.neurons.iter().zip(&other.neurons).all(|(a, b)| a.id == b.id)
+            && self.connections.iter().zip(&other.connections).all(|(a, b)| {
+                a.from == b.from && a.to == b.to && a.recurrent == b.recurrent
+            })
+    }
+
+    /// Hash of the topology, i.e. the order of all neurons and the endpoints of all connections.
+    /// Brains with the same topology have the same fingerprint, genes are ignored.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
     /// Gain direct access to the internal network data.
     /// This will always trigger a reordering of all data.
     pub fn raw(&mut self) -> RawBrainAccess<'_, A, P> {
@@ -71,11 +232,447 @@ where
             connections: &mut self.connections,
             order:       &mut self.order,
             inputs:      ThinVec::new(),
+            fingerprint: &mut self.fingerprint,
         }
     }
-    // TODO: provide stable operations on `Brain`
+
+    pub fn neuron(&self, id: NeuronID) -> Option<&Neuron<A>> {
+        self.order.index(id).map(|index| &self.neurons[index])
+    }
+
+    /// Changing the gene of a [`Neuron`] never affects the topology, so direct access is always safe.
+    pub fn activator_gene_mut(&mut self, id: NeuronID) -> Option<&mut A::Gene> {
+        self.order.index(id).map(|index| &mut self.neurons[index].activator_gene)
+    }
+
+    /// Returns all connections ending in `to`.
+    pub fn connections_to(&self, to: NeuronID) -> &[Connection<P>] {
+        let Some(index) = self.order.index(to) else { return &[] };
+        let start = self.connection_bound(index);
+        let end = self.connection_bound(index + 1);
+        &self.connections[start..end]
+    }
+
+    /// Returns the index of the first [`Connection`] from `from` to `to`.
+    pub fn find_connection(&self, from: NeuronID, to: NeuronID) -> Option<usize> {
+        let index = self.order.index(to)?;
+        let start = self.connection_bound(index);
+        self.connections[start..]
+            .iter()
+            .take_while(|conn| conn.to == to)
+            .position(|conn| conn.from == from)
+            .map(|i| start + i)
+    }
+
+    /// Adds a new unconnected [`Neuron`] at the end of the order.
+    /// # Panics
+    /// Panics when there are no free [`NeuronID`]s left.
+    pub fn add_neuron(&mut self, activator_gene: A::Gene) -> NeuronID {
+        self.insert_neuron(self.neurons.len(), activator_gene)
+    }
+
+    /// Removes a [`Neuron`] together with all connections that start, end or are modulated by it.
+    pub fn remove_neuron(&mut self, id: NeuronID) -> Option<Neuron<A>> {
+        let index = self.order.index(id)?;
+        self.connections.retain(|conn| {
+            conn.from != id
+                && conn.to != id
+                && P::referenced_neurons(&conn.propagator_gene).all(|other| *other.borrow() != id)
+        });
+        let neuron = self.neurons.remove(index);
+        // SAFETY: every neuron after `index` moves down by one, so all indices stay unique
+        unsafe {
+            self.order.set_unchecked(id, None);
+            for (i, neuron) in self.neurons.iter().enumerate().skip(index) {
+                self.order.set_unchecked(neuron.id, Some(i));
+            }
+        }
+        self.order.truncate();
+        self.refresh_fingerprint();
+        Some(neuron)
+    }
+
+    /// Adds a new [`Connection`] while keeping the topological order valid.
+    /// Neurons will be moved in the order when necessary, even when this fails.
+    /// Returns the index of the new connection.
+    pub fn add_connection(
+        &mut self,
+        from: NeuronID,
+        to: NeuronID,
+        propagator_gene: P::Gene,
+    ) -> Result<usize, BrainError> {
+        self.insert_connection(Connection { from, to, recurrent: false, propagator_gene })
+    }
+
+    /// Adds a new recurrent [`Connection`], which receives the output of the previous step.
+    /// This never changes the order, so it can close any cycle.
+    /// Returns the index of the new connection.
+    pub fn add_recurrent_connection(
+        &mut self,
+        from: NeuronID,
+        to: NeuronID,
+        propagator_gene: P::Gene,
+    ) -> Result<usize, BrainError> {
+        self.insert_connection(Connection { from, to, recurrent: true, propagator_gene })
+    }
+
+    /// Removes the first [`Connection`] from `from` to `to`.
+    pub fn remove_connection(&mut self, from: NeuronID, to: NeuronID) -> Option<Connection<P>> {
+        let index = self.find_connection(from, to)?;
+        let conn = self.connections.remove(index);
+        self.refresh_fingerprint();
+        Some(conn)
+    }
+
+    /// Changes the gene of a [`Connection`] while keeping the topological order valid.
+    /// The gene will be reverted when the new modulation inputs would lead to a cycle.
+    /// Returns the new index of the connection.
+    pub fn update_connection(
+        &mut self,
+        index: usize,
+        update: impl FnOnce(&mut P::Gene),
+    ) -> Result<usize, BrainError> {
+        let mut conn = self.connections.remove(index);
+        let previous = conn.propagator_gene.clone();
+        update(&mut conn.propagator_gene);
+        let Connection { from, to, recurrent, .. } = conn;
+        self.insert_connection(conn).inspect_err(|_| {
+            self.insert_connection(Connection { from, to, recurrent, propagator_gene: previous })
+                .expect("previous gene should be valid");
+        })
+    }
+
+    /// Replaces the [`Connection`] `from -> to` with a new [`Neuron`] and two connections
+    /// `from -> new` and `new -> to`.
+    /// `split` receives the gene of the old connection and returns the genes for the new connections.
+    /// Returns the [`NeuronID`] of the new neuron.
+    ///
+    /// When one of the new connections can't be added, the new neuron is removed and the old
+    /// connection is restored. Like [`Brain::add_connection`] this can still move neurons.
+    pub fn split_connection(
+        &mut self,
+        from: NeuronID,
+        to: NeuronID,
+        activator_gene: A::Gene,
+        split: impl FnOnce(P::Gene) -> (P::Gene, P::Gene),
+    ) -> Result<NeuronID, BrainError> {
+        let conn = self
+            .remove_connection(from, to)
+            .ok_or(BrainError::MissingConnection { from, to })?;
+        let index = if conn.recurrent {
+            self.order.index(from).expect("source should still be part of the order") + 1
+        } else {
+            self.order.index(to).expect("target should still be part of the order")
+        };
+        let id = self.insert_neuron(index, activator_gene);
+        let recurrent = conn.recurrent;
+        let previous = conn.propagator_gene.clone();
+        let (incoming, outgoing) = split(conn.propagator_gene);
+        let result = self.add_connection(from, id, incoming).and_then(|_| {
+            self.insert_connection(Connection { from: id, to, recurrent, propagator_gene: outgoing })
+        });
+        if let Err(error) = result {
+            self.remove_neuron(id);
+            self.insert_connection(Connection { from, to, recurrent, propagator_gene: previous })
+                .expect("previous connection should be valid");
+            return Err(error);
+        }
+        Ok(id)
+    }
+
+    fn insert_connection(&mut self, conn: Connection<P>) -> Result<usize, BrainError> {
+        self.order.index(conn.from).ok_or(BrainError::MissingNeuron(conn.from))?;
+        self.order.index(conn.to).ok_or(BrainError::MissingNeuron(conn.to))?;
+        let dependencies = conn.dependencies().collect::<ThinVec<_>>();
+        for &(id, _) in &dependencies {
+            self.order.index(id).ok_or(BrainError::MissingNeuron(id))?;
+        }
+        for (i, &(before, after)) in dependencies.iter().enumerate() {
+            self.ensure_before(before, after, &dependencies[..i])?;
+        }
+        let index = self.connection_bound(
+            self.order.index(conn.to).expect("target should still be part of the order") + 1,
+        );
+        self.connections.insert(index, conn);
+        self.refresh_fingerprint();
+        Ok(index)
+    }
+
+    /// # Panics
+    /// Panics when there are no free [`NeuronID`]s left.
+    fn insert_neuron(&mut self, index: usize, activator_gene: A::Gene) -> NeuronID {
+        let id = self.order.next_free(None).expect("there should be a free NeuronID left");
+        self.neurons.insert(index, Neuron { id, activator_gene });
+        // SAFETY: every neuron after `index` moves up by one, so all indices stay unique
+        unsafe {
+            for (i, neuron) in self.neurons.iter().enumerate().skip(index) {
+                self.order.set_unchecked(neuron.id, Some(i));
+            }
+        }
+        self.refresh_fingerprint();
+        id
+    }
+
+    /// Removes all connections without effect and all hidden neurons that are not influenced by
+    /// any sensor or don't influence any action of `body`. Interface neurons are never removed.
+    ///
+    /// Hidden neurons without inputs still output a constant depending on their gene,
+    /// so removing them can change the result of the network.
+    /// # Panics
+    /// Panics if `body` references neurons that are not part of this brain.
+    pub fn prune<X: Phenotype>(&mut self, body: &Body<X>) -> PruneReport {
+        let mut report = PruneReport::default();
+        // NOTE: removing connections only removes dependencies, so the order stays valid
+        self.connections.retain(|conn| {
+            let silent = P::is_silent(&conn.propagator_gene);
+            if silent {
+                report.silent.push((conn.from, conn.to));
+            }
+            !silent
+        });
+
+        let index = |id| self.order.index(id).expect("body should only reference this brain");
+        let len = self.neurons.len();
+        let mut forward = vec![ThinVec::new(); len];
+        let mut backward = vec![ThinVec::new(); len];
+        for conn in &self.connections {
+            let to = index(conn.to);
+            let modulators = P::referenced_neurons(&conn.propagator_gene).map(|id| *id.borrow());
+            for from in [conn.from].into_iter().chain(modulators) {
+                forward[index(from)].push(to);
+                backward[to].push(index(from));
+            }
+        }
+        let reach = |edges: &[ThinVec<usize>], start: &mut dyn Iterator<Item = NeuronID>| {
+            let mut reached = BitSet::with_capacity(len);
+            let mut open = start.map(index).filter(|i| reached.insert(*i)).collect::<Vec<_>>();
+            while let Some(current) = open.pop() {
+                for &next in &edges[current] {
+                    if reached.insert(next) {
+                        open.push(next);
+                    }
+                }
+            }
+            reached
+        };
+        let from_sensors = reach(&forward, &mut body.iter_sensor_neurons());
+        let to_actions = reach(&backward, &mut body.iter_action_neurons());
+        let interface =
+            body.iter_sensor_neurons().chain(body.iter_action_neurons()).collect::<HashSet<_>>();
+
+        let mut removed = HashSet::new();
+        for (i, neuron) in self.neurons.iter().enumerate() {
+            if interface.contains(&neuron.id) {
+                continue;
+            }
+            if !from_sensors.contains(i) {
+                report.unreachable.push(neuron.id);
+            } else if !to_actions.contains(i) {
+                report.dead_ends.push(neuron.id);
+            } else {
+                continue;
+            }
+            removed.insert(neuron.id);
+        }
+        for conn in &self.connections {
+            let mut neurons = [conn.from, conn.to]
+                .into_iter()
+                .chain(P::referenced_neurons(&conn.propagator_gene).map(|id| *id.borrow()));
+            if neurons.any(|id| removed.contains(&id)) {
+                report.detached.push((conn.from, conn.to));
+            }
+        }
+        for id in report.unreachable.iter().chain(&report.dead_ends) {
+            self.remove_neuron(*id);
+        }
+        self.refresh_fingerprint();
+        report
+    }
+
+    /// Checks all invariants assumed by [`Brain::new_unchecked`].
+    ///
+    /// Dependencies that are out of order are reported as [`BrainError::Cycle`]
+    /// when no valid order exists at all.
+    pub fn validate(&self) -> Result<(), BrainError> {
+        for (index, neuron) in self.neurons.iter().enumerate() {
+            match self.order.index(neuron.id) {
+                None => return Err(BrainError::MissingNeuron(neuron.id)),
+                Some(i) if i < index && self.neurons[i].id == neuron.id =>
+                    return Err(BrainError::DuplicateNeuron(neuron.id)),
+                Some(i) if i != index => return Err(BrainError::InvalidOrder(neuron.id)),
+                Some(_) => (),
+            }
+        }
+        // NOTE: all neurons are mapped correctly, so the remaining entries have no neuron
+        if let Some(id) = self.order.iter_used().find(|id| {
+            let neuron = self.order.index(*id).and_then(|index| self.neurons.get(index));
+            neuron.is_none_or(|neuron| neuron.id != *id)
+        }) {
+            return Err(BrainError::InvalidOrder(id));
+        }
+        let mut previous = 0;
+        for (position, conn) in self.connections.iter().enumerate() {
+            let modulators = P::referenced_neurons(&conn.propagator_gene).map(|id| *id.borrow());
+            for neuron in [conn.from, conn.to].into_iter().chain(modulators) {
+                if self.order.index(neuron).is_none() {
+                    return Err(BrainError::DanglingConnection { connection: position, neuron });
+                }
+            }
+            let to = self.order.index(conn.to).expect("all neurons were checked before");
+            if to < previous {
+                return Err(BrainError::UnsortedConnection(position));
+            }
+            previous = to;
+        }
+        if let Some((from, to)) = self.find_cycle() {
+            return Err(BrainError::Cycle { from, to });
+        }
+        let index = |id| self.order.index(id).expect("all neurons were checked before");
+        for (position, conn) in self.connections.iter().enumerate() {
+            for modulator in P::referenced_neurons(&conn.propagator_gene) {
+                let modulator = *modulator.borrow();
+                if !conn.recurrent && index(modulator) >= index(conn.to) {
+                    let connection = position;
+                    return Err(BrainError::ModulationOutOfOrder { connection, modulator });
+                }
+            }
+            for (before, after) in conn.dependencies() {
+                if index(before) >= index(after) {
+                    return Err(BrainError::OutOfOrder { before, after });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a dependency `(before, after)` that is part of a cycle.
+    /// Assumes that all connections only reference neurons that are part of the order.
+    fn find_cycle(&self) -> Option<(NeuronID, NeuronID)> {
+        let len = self.neurons.len();
+        let mut incoming = vec![ThinVec::new(); len];
+        let mut outgoing = vec![ThinVec::new(); len];
+        let mut degree = vec![0usize; len];
+        for (before, after) in self.connections.iter().flat_map(Connection::dependencies) {
+            // SAFETY: all dependencies only reference neurons that are part of the order
+            let (before, after) =
+                unsafe { (self.order.index_unchecked(before), self.order.index_unchecked(after)) };
+            incoming[after].push(before);
+            outgoing[before].push(after);
+            degree[after] += 1;
+        }
+        let mut open = (0..len).filter(|i| degree[*i] == 0).collect::<Vec<_>>();
+        while let Some(current) = open.pop() {
+            for &next in &outgoing[current] {
+                degree[next] -= 1;
+                if degree[next] == 0 {
+                    open.push(next);
+                }
+            }
+        }
+        // NOTE: every unresolved neuron has an unresolved dependency,
+        // so walking them backwards has to end in a cycle
+        let mut current = (0..len).find(|i| degree[*i] > 0)?;
+        let mut visited = BitSet::with_capacity(len);
+        visited.insert(current);
+        loop {
+            let previous = *incoming[current]
+                .iter()
+                .find(|i| degree[**i] > 0)
+                .expect("unresolved neurons should have an unresolved dependency");
+            if !visited.insert(previous) {
+                return Some((self.neurons[previous].id, self.neurons[current].id));
+            }
+            current = previous;
+        }
+    }
+
+    /// Returns the index of the first [`Connection`] that ends at a neuron with an index of at least `index`.
+    fn connection_bound(&self, index: usize) -> usize {
+        self.connections.partition_point(|conn| {
+            // SAFETY: all connections only reference neurons that are part of the order
+            unsafe { self.order.index_unchecked(conn.to) < index }
+        })
+    }
+
+    /// Moves neurons so that `before` is evaluated before `after`.
+    /// `extra` contains additional dependencies that are not part of any connection yet.
+    ///
+    /// Only neurons between `after` and `before` are affected:
+    /// everything that depends on `after` is moved behind `before` keeping relative order intact.
+    fn ensure_before(
+        &mut self,
+        before: NeuronID,
+        after: NeuronID,
+        extra: &[(NeuronID, NeuronID)],
+    ) -> Result<(), BrainError> {
+        let cycle = BrainError::Cycle { from: before, to: after };
+        // SAFETY: both neurons are validated by the caller
+        let (lower, upper) =
+            unsafe { (self.order.index_unchecked(after), self.order.index_unchecked(before)) };
+        if upper < lower {
+            return Ok(());
+        }
+        if lower == upper {
+            return Err(cycle);
+        }
+        let len = upper - lower + 1;
+        let mut edges = vec![ThinVec::new(); len];
+        let dependencies =
+            self.connections.iter().flat_map(Connection::dependencies).chain(extra.iter().copied());
+        for (from, to) in dependencies {
+            // SAFETY: all dependencies only reference neurons that are part of the order
+            let (from, to) =
+                unsafe { (self.order.index_unchecked(from), self.order.index_unchecked(to)) };
+            if lower <= from && to <= upper {
+                edges[from - lower].push(to - lower);
+            }
+        }
+        let mut moved = BitSet::with_capacity(len);
+        moved.insert(0);
+        let mut open = vec![0];
+        while let Some(current) = open.pop() {
+            for &next in &edges[current] {
+                if moved.insert(next) {
+                    open.push(next);
+                }
+            }
+        }
+        if moved.contains(len - 1) {
+            return Err(cycle);
+        }
+        let start = self.connection_bound(lower);
+        let end = self.connection_bound(upper + 1);
+        let (moved, kept): (Vec<_>, Vec<_>) = self
+            .neurons
+            .drain(lower..=upper)
+            .enumerate()
+            .partition(|(i, _)| moved.contains(*i));
+        self.neurons
+            .splice(lower..lower, kept.into_iter().chain(moved).map(|(_, neuron)| neuron));
+        // SAFETY: neurons are only permuted inside the range, so all indices stay unique
+        unsafe {
+            for (i, neuron) in self.neurons[lower..=upper].iter().enumerate() {
+                self.order.set_unchecked(neuron.id, Some(lower + i));
+            }
+        }
+        // SAFETY: all connections only reference neurons that are part of the order
+        self.connections[start..end]
+            .sort_by_cached_key(|conn| unsafe { self.order.index_unchecked(conn.to) });
+        self.refresh_fingerprint();
+        Ok(())
+    }
+
+    fn refresh_fingerprint(&mut self) {
+        self.fingerprint = fingerprint(&self.neurons, &self.connections);
+    }
 }
 
+/// Direct access to the internal network data.
+///
+/// When dropped all neurons are sorted in topological order, placing `inputs` as early as possible
+/// and keeping the previous order otherwise.
+/// # Panics
+/// Dropping panics when connections that are not recurrent form a cycle.
 #[derive(Debug)]
 pub struct RawBrainAccess<'b, A, P>
 where
@@ -86,6 +683,7 @@ where
     pub connections: &'b mut ThinVec<Connection<P>>,
     pub order:       &'b mut NeuronOrder,
     pub inputs:      ThinVec<NeuronID>,
+    fingerprint:     &'b mut u64,
 }
 impl<A, P> Drop for RawBrainAccess<'_, A, P>
 where
@@ -93,28 +691,35 @@ where
     P: Propagator,
 {
     fn drop(&mut self) {
-        let mut order = Vec::with_capacity(self.neurons.len());
-        let mut open = VecDeque::from_iter(
+        let len = self.neurons.len();
+        let mut edges = vec![ThinVec::new(); len];
+        let mut degree = vec![0usize; len];
+        for (before, after) in self.connections.iter().flat_map(Connection::dependencies) {
+            let before =
+                self.order.index(before).expect("all connections should be in the ordering");
+            let after = self.order.index(after).expect("all connections should be in the ordering");
+            edges[before].push(after);
+            degree[after] += 1;
+        }
+        let inputs = BitSet::<u32>::from_iter(
             self.inputs
                 .iter()
-                .copied()
-                .map(|id| self.order.index(id).expect("all inputs should be in the ordering")),
+                .map(|id| self.order.index(*id).expect("all inputs should be in the ordering")),
         );
-        let mut seen = BitSet::with_capacity(self.neurons.len());
-        seen.extend(open.iter().copied());
-        while let Some(current) = open.pop_front() {
-            let id = self.neurons[current].id;
-            order.push(id);
-            for next in
-                self.connections.iter().filter_map(|conn| (conn.from == id).then_some(conn.to))
-            {
-                let index =
-                    self.order.index(next).expect("all connections should be in the ordering");
-                if seen.insert(index) {
-                    open.push_back(index);
+        // NOTE: inputs first, otherwise keep the previous order
+        let priority = |index: usize| Reverse((!inputs.contains(index), index));
+        let mut open = BinaryHeap::from_iter((0..len).filter(|i| degree[*i] == 0).map(priority));
+        let mut order = Vec::with_capacity(len);
+        while let Some(Reverse((_, current))) = open.pop() {
+            order.push(self.neurons[current].id);
+            for &next in &edges[current] {
+                degree[next] -= 1;
+                if degree[next] == 0 {
+                    open.push(priority(next));
                 }
             }
         }
+        assert_eq!(len, order.len(), "only recurrent connections should form cycles");
         let map = self.order.rebuild(order);
         self.neurons.iter_mut().for_each(|neuron| neuron.id = map[&neuron.id]);
         self.connections.iter_mut().for_each(|conn| {
@@ -123,8 +728,24 @@ where
             P::remap_gene(&mut conn.propagator_gene, &map);
         });
         self.neurons.sort_unstable_by_key(|neuron| neuron.id);
-        self.connections.sort_by_key(|conn| conn.from);
+        self.connections.sort_by_key(|conn| conn.to);
+        *self.fingerprint = fingerprint(self.neurons, self.connections);
+    }
+}
+
+/// See [`Brain::fingerprint`].
+fn fingerprint<A: Activator, P: Propagator>(
+    neurons: &[Neuron<A>],
+    connections: &[Connection<P>],
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    neurons.len().hash(&mut hasher);
+    neurons.iter().for_each(|neuron| neuron.id.hash(&mut hasher));
+    connections.len().hash(&mut hasher);
+    for conn in connections {
+        (conn.from, conn.to, conn.recurrent).hash(&mut hasher);
     }
+    hasher.finish()
 }
 
 #[cfg(test)]
@@ -187,6 +808,45 @@ mod test {
             input
         }
     }
+
+    /// Connections modulated by at most one neuron.
+    #[derive(Debug, Default)]
+    struct Gated;
+    impl Propagator for Gated {
+        type Config = ();
+        type Gene = Option<NeuronID>;
+        type Input<'i>
+            = f64
+        where
+            Self: 'i;
+        type Output<'o>
+            = f64
+        where
+            Self: 'o;
+
+        fn modulation(
+            &self,
+            gene: &Self::Gene,
+            _config: &Self::Config,
+        ) -> impl Iterator<Item: std::borrow::Borrow<NeuronID>> {
+            gene.iter()
+        }
+
+        fn referenced_neurons(gene: &Self::Gene) -> impl Iterator<Item: Borrow<NeuronID>> {
+            gene.iter()
+        }
+
+        fn propagate(
+            &mut self,
+            input: Self::Input<'_>,
+            _modulation: &[Self::Input<'_>],
+            _gene: &Self::Gene,
+            _config: &Self::Config,
+        ) -> Self::Output<'_> {
+            input
+        }
+    }
+
     #[test]
     fn raw_brain_access_leaves_brain_ordered() {
         let mut brain = Brain::<DummyData, DummyData>::new();
@@ -203,18 +863,261 @@ mod test {
             access.neurons.push(Neuron { id: id0, activator_gene: () });
             access.neurons.push(Neuron { id: id1, activator_gene: () });
             access.neurons.push(Neuron { id: id2, activator_gene: () });
-            access.connections.push(Connection { from: id1, to: id0, propagator_gene: () });
-            access.connections.push(Connection { from: id0, to: id1, propagator_gene: () });
-            access.connections.push(Connection { from: id0, to: id2, propagator_gene: () });
+            access.connections.push(Connection {
+                from:            id1,
+                to:              id0,
+                recurrent:       false,
+                propagator_gene: (),
+            });
+            access.connections.push(Connection {
+                from:            id0,
+                to:              id1,
+                recurrent:       true,
+                propagator_gene: (),
+            });
+            access.connections.push(Connection {
+                from:            id0,
+                to:              id2,
+                recurrent:       false,
+                propagator_gene: (),
+            });
             access.inputs.push(id1);
         }
         let ids = brain.order().iter_used().collect::<Box<_>>();
         let conns = brain.connections();
-        assert_eq!(ids[0], conns[0].from);
-        assert_eq!(ids[1], conns[0].to);
-        assert_eq!(ids[1], conns[1].from);
-        assert_eq!(ids[0], conns[1].to);
+        assert_eq!(ids[1], conns[0].from);
+        assert_eq!(ids[0], conns[0].to);
+        assert_eq!(ids[0], conns[1].from);
+        assert_eq!(ids[1], conns[1].to);
         assert_eq!(ids[1], conns[2].from);
         assert_eq!(ids[2], conns[2].to);
     }
+
+    fn assert_ordered<P: Propagator>(brain: &Brain<DummyData, P>) {
+        let order = brain.order();
+        for (i, neuron) in brain.neurons().iter().enumerate() {
+            assert_eq!(Some(i), order.index(neuron.id));
+        }
+        for conn in brain.connections() {
+            for (before, after) in conn.dependencies() {
+                assert_eq!(Some(std::cmp::Ordering::Less), order.cmp(before, after), "{conn:?}");
+            }
+        }
+        assert!(brain.connections().is_sorted_by_key(|conn| order.index(conn.to)));
+    }
+
+    #[test]
+    fn add_connection_reorders_neurons() {
+        let mut brain = Brain::<DummyData, DummyData>::new();
+        let ids = (0..4).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[1], ids[2], ()).unwrap();
+        brain.add_connection(ids[3], ids[1], ()).unwrap();
+        assert_ordered(&brain);
+        brain.add_connection(ids[2], ids[0], ()).unwrap();
+        assert_ordered(&brain);
+        assert_eq!(ids[3], brain.neurons()[0].id);
+        assert_eq!(ids[0], brain.neurons()[3].id);
+        assert_eq!(Some(0), brain.find_connection(ids[3], ids[1]));
+        assert_eq!(1, brain.connections_to(ids[0]).len());
+    }
+
+    #[test]
+    fn add_connection_rejects_cycles() {
+        let mut brain = Brain::<DummyData, DummyData>::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[0], ids[1], ()).unwrap();
+        brain.add_connection(ids[1], ids[2], ()).unwrap();
+        assert_eq!(
+            Err(BrainError::Cycle { from: ids[2], to: ids[0] }),
+            brain.add_connection(ids[2], ids[0], ())
+        );
+        assert_eq!(
+            Err(BrainError::Cycle { from: ids[1], to: ids[1] }),
+            brain.add_connection(ids[1], ids[1], ())
+        );
+        assert_eq!(2, brain.connections().len());
+        assert_ordered(&brain);
+    }
+
+    #[test]
+    fn remove_neuron_removes_connections() {
+        let mut brain = Brain::<DummyData, DummyData>::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[0], ids[1], ()).unwrap();
+        brain.add_connection(ids[1], ids[2], ()).unwrap();
+        brain.add_connection(ids[0], ids[2], ()).unwrap();
+        assert_eq!(ids[1], brain.remove_neuron(ids[1]).unwrap().id);
+        assert!(brain.remove_neuron(ids[1]).is_none());
+        assert_eq!(1, brain.connections().len());
+        assert_eq!(None, brain.order().index(ids[1]));
+        assert_ordered(&brain);
+        assert_eq!(ids[1], brain.add_neuron(()));
+    }
+
+    #[test]
+    fn split_connection_inserts_neuron() {
+        let mut brain = Brain::<DummyData, DummyData>::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[2], ids[0], ()).unwrap();
+        let id = brain.split_connection(ids[2], ids[0], (), |gene| (gene, ())).unwrap();
+        assert_ordered(&brain);
+        assert_eq!(None, brain.find_connection(ids[2], ids[0]));
+        assert!(brain.find_connection(ids[2], id).is_some());
+        assert!(brain.find_connection(id, ids[0]).is_some());
+        assert_eq!(
+            Err(BrainError::MissingConnection { from: ids[2], to: ids[0] }),
+            brain.split_connection(ids[2], ids[0], (), |gene| (gene, ()))
+        );
+    }
+
+    #[test]
+    fn failed_split_restores_connection() {
+        // <0> -> <1> -> <2>
+        let mut brain = Brain::<DummyData, Gated>::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[0], ids[1], None).unwrap();
+        brain.add_connection(ids[1], ids[2], None).unwrap();
+        let fingerprint = brain.fingerprint();
+        // <2> can't modulate the input of <1>, since it already depends on <1>
+        assert_eq!(
+            Err(BrainError::Cycle { from: ids[2], to: ids[1] }),
+            brain.split_connection(ids[0], ids[1], (), |gene| (gene, Some(ids[2])))
+        );
+        assert_ordered(&brain);
+        assert_eq!(3, brain.neurons().len());
+        assert_eq!(2, brain.connections().len());
+        assert!(brain.find_connection(ids[0], ids[1]).is_some());
+        assert_eq!(fingerprint, brain.fingerprint());
+    }
+
+    #[test]
+    fn recurrent_connections_keep_the_order() {
+        let mut brain = Brain::<DummyData, DummyData>::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[0], ids[1], ()).unwrap();
+        brain.add_connection(ids[1], ids[2], ()).unwrap();
+        brain.add_recurrent_connection(ids[2], ids[0], ()).unwrap();
+        brain.add_recurrent_connection(ids[1], ids[1], ()).unwrap();
+        brain.add_recurrent_connection(ids[1], ids[0], ()).unwrap();
+        assert_ordered(&brain);
+        // NOTE: recurrent connections don't have to close a cycle
+        brain.add_recurrent_connection(ids[0], ids[2], ()).unwrap();
+        let last = brain.add_neuron(());
+        brain.add_recurrent_connection(ids[0], last, ()).unwrap();
+        brain.add_recurrent_connection(last, ids[0], ()).unwrap();
+        assert_ordered(&brain);
+        let order = brain.neurons().iter().map(|neuron| neuron.id).collect::<Vec<_>>();
+        assert_eq!([ids[0], ids[1], ids[2], last], *order);
+        let id = brain.split_connection(ids[2], ids[0], (), |gene| (gene, ())).unwrap();
+        assert_ordered(&brain);
+        assert!(!brain.connections()[brain.find_connection(ids[2], id).unwrap()].recurrent);
+        assert!(brain.connections()[brain.find_connection(id, ids[0]).unwrap()].recurrent);
+    }
+
+    #[test]
+    fn prune_removes_unused_parts() {
+        use crate::{
+            numeric::{Weight, Weighted},
+            test_util::TestPhenotype,
+        };
+
+        // sensors: <0>, <1>
+        // <3> = <0>, <4> = <1>, <5> unconnected
+        // outputs: <2> = <3> + <5> + 0 * <1>
+        let mut brain = Brain::<DummyData, Weighted>::new();
+        let ids = (0..6).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[0], ids[3], Weight(1.0)).unwrap();
+        brain.add_connection(ids[3], ids[2], Weight(1.0)).unwrap();
+        brain.add_connection(ids[1], ids[4], Weight(1.0)).unwrap();
+        brain.add_connection(ids[5], ids[2], Weight(1.0)).unwrap();
+        brain.add_connection(ids[1], ids[2], Weight(0.0)).unwrap();
+        let body = Body::builder(TestPhenotype)
+            .sensor(ids[0], ())
+            .sensor(ids[1], ())
+            .action(ids[2], ())
+            .build(brain.order())
+            .unwrap();
+
+        let report = brain.prune(&body);
+        assert_eq!(report.unreachable.as_slice(), &[ids[5]]);
+        assert_eq!(report.dead_ends.as_slice(), &[ids[4]]);
+        assert_eq!(report.silent.as_slice(), &[(ids[1], ids[2])]);
+        let mut detached = report.detached.to_vec();
+        detached.sort();
+        assert_eq!(detached, vec![(ids[1], ids[4]), (ids[5], ids[2])]);
+        assert_eq!(Ok(()), brain.validate());
+        assert_eq!(4, brain.neurons().len());
+        assert_eq!(2, brain.connections().len());
+        body.validate(brain.order()).unwrap();
+        assert!(brain.prune(&body).is_empty());
+    }
+
+    #[test]
+    fn validate_reports_broken_invariants() {
+        use crate::numeric::{Modulated, ModulatedWeight};
+
+        let gene = |modulator| ModulatedWeight { weight: 1.0, modulator };
+        let mut brain = Brain::<DummyData, Modulated>::new();
+        let ids = (0..4).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[0], ids[1], gene(None)).unwrap();
+        brain.add_connection(ids[1], ids[2], gene(Some(ids[0]))).unwrap();
+        assert_eq!(Ok(()), brain.validate());
+        let free = brain.order().next_free(None).unwrap();
+        let check = |update: &dyn Fn(&mut Brain<DummyData, Modulated>)| {
+            let mut copy = brain.clone();
+            update(&mut copy);
+            Brain::try_new(copy.neurons, copy.connections, copy.order).err()
+        };
+
+        let missing = check(&|brain| brain.neurons[3].id = free);
+        assert_eq!(Some(BrainError::MissingNeuron(free)), missing);
+        let duplicate = check(&|brain| brain.neurons[3].id = ids[2]);
+        assert_eq!(Some(BrainError::DuplicateNeuron(ids[2])), duplicate);
+        let dangling = check(&|brain| brain.connections[1].propagator_gene.modulator = Some(free));
+        assert_eq!(Some(BrainError::DanglingConnection { connection: 1, neuron: free }), dangling);
+        let modulation =
+            check(&|brain| brain.connections[1].propagator_gene.modulator = Some(ids[3]));
+        assert_eq!(
+            Some(BrainError::ModulationOutOfOrder { connection: 1, modulator: ids[3] }),
+            modulation
+        );
+        let swapped = check(&|brain| {
+            brain.neurons.swap(0, 1);
+            brain.order.swap(ids[0], ids[1]);
+        });
+        assert_eq!(Some(BrainError::OutOfOrder { before: ids[0], after: ids[1] }), swapped);
+        let cycle = check(&|brain| {
+            brain.connections.insert(0, Connection {
+                from:            ids[2],
+                to:              ids[0],
+                recurrent:       false,
+                propagator_gene: gene(None),
+            });
+        });
+        assert!(matches!(cycle, Some(BrainError::Cycle { .. })), "{cycle:?}");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_checks_invariants() {
+        let mut brain = Brain::<DummyData, DummyData>::new();
+        let ids = (0..3).map(|_| brain.add_neuron(())).collect::<Vec<_>>();
+        brain.add_connection(ids[2], ids[0], ()).unwrap();
+        brain.add_connection(ids[0], ids[1], ()).unwrap();
+        let json = serde_json::to_value(&brain).unwrap();
+        let copy = serde_json::from_value::<Brain<DummyData, DummyData>>(json.clone()).unwrap();
+        let ids = |brain: &Brain<_, _>| brain.neurons().iter().map(|n| n.id).collect::<Vec<_>>();
+        assert_eq!(ids(&brain), ids(&copy));
+        assert_ordered(&copy);
+
+        let mut unsorted = json.clone();
+        unsorted["connections"].as_array_mut().unwrap().reverse();
+        let error = serde_json::from_value::<Brain<DummyData, DummyData>>(unsorted).unwrap_err();
+        assert!(error.to_string().contains("not sorted"), "{error}");
+
+        let mut reordered = json;
+        reordered["neurons"].as_array_mut().unwrap().swap(0, 1);
+        let error = serde_json::from_value::<Brain<DummyData, DummyData>>(reordered).unwrap_err();
+        assert!(error.to_string().contains("does not match"), "{error}");
+    }
 }
@@ -36,20 +36,78 @@ pub trait Propagator: Debug + Default {
 
     #[expect(unused_variables)]
     fn remap_gene(gene: &mut Self::Gene, map: &HashMap<NeuronID, NeuronID>) {}
+
+    /// Returns iterator over all [`NeuronID`]s referenced by `gene`.
+    /// This is used to keep the topological order valid and has to include everything
+    /// that [`modulation`] can return for this gene.
+    #[expect(unused_variables)]
+    fn referenced_neurons(gene: &Self::Gene) -> impl Iterator<Item: Borrow<NeuronID>> {
+        std::iter::empty::<NeuronID>()
+    }
+
+    /// Returns `true` when a connection using `gene` never has an effect on its target,
+    /// so it can be removed by [`Brain::prune`](super::Brain::prune).
+    #[expect(unused_variables)]
+    fn is_silent(gene: &Self::Gene) -> bool {
+        false
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Connection data used both as static data during simulation and as a direct gene.
+///
+/// Recurrent connections close cycles in the network.
+/// They always receive the output `from` had at the end of the previous simulation step,
+/// while all other connections receive the output of the current step.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "P::Gene: serde::Serialize",
+        deserialize = "P::Gene: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Connection<P: Propagator> {
     pub from: NeuronID,
     pub to: NeuronID,
+    pub recurrent: bool,
     pub propagator_gene: P::Gene,
 }
+impl<P> Clone for Connection<P>
+where
+    P: Propagator,
+{
+    fn clone(&self) -> Self {
+        Self {
+            from:            self.from,
+            to:              self.to,
+            recurrent:       self.recurrent,
+            propagator_gene: self.propagator_gene.clone(),
+        }
+    }
+}
+impl<P> Connection<P>
+where
+    P: Propagator,
+{
+    /// Returns all pairs `(before, after)` where `before` has to be evaluated before `after`.
+    ///
+    /// Recurrent connections (including their modulation inputs) only read outputs
+    /// of the previous step, so they never constrain the order.
+    pub fn dependencies(&self) -> impl Iterator<Item = (NeuronID, NeuronID)> {
+        let recurrent = self.recurrent;
+        let modulation =
+            P::referenced_neurons(&self.propagator_gene).map(|id| (*id.borrow(), self.to));
+        std::iter::once((self.from, self.to)).chain(modulation).filter(move |_| !recurrent)
+    }
+}
 impl<P> Display for Connection<P>
 where
     P: Propagator<Gene: Display>,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} -> {}: {}", self.from, self.to, self.propagator_gene)
+        let arrow = if self.recurrent { "~>" } else { "->" };
+        write!(f, "{} {arrow} {}: {}", self.from, self.to, self.propagator_gene)
     }
 }
 
@@ -166,8 +224,8 @@ mod test {
             ModulatorGene(NeuronID::try_from(0)),
             ModulatorGene(NeuronID::try_from(2)),
         ];
-        let mut propagator = ModulatorPropagator::default();
-        let others = vec![0.7, 0.3, -1.2];
+        let mut propagator = ModulatorPropagator;
+        let others = [0.7, 0.3, -1.2];
         let mut modulation = vec![0.0];
         for input in inputs {
             for gene in &genes {
@@ -0,0 +1,178 @@
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt::{Debug, Display},
+};
+
+use super::*;
+
+/// Renders the [`Brain`] of an agent in the DOT language used by Graphviz.
+///
+/// Sensors and actions of the [`Body`] are highlighted, recurrent connections are dashed and
+/// modulation inputs (see [`Propagator::modulation`]) are drawn as dotted edges into the target
+/// of the modulated connection.
+pub struct Dot<'b, A, P, X>
+where
+    A: Activator,
+    P: Propagator,
+    X: Phenotype,
+{
+    brain:            &'b Brain<A, P>,
+    body:             &'b Body<X>,
+    config:           &'b P::Config,
+    neuron_label:     Option<fn(&A::Gene) -> String>,
+    connection_label: Option<fn(&P::Gene) -> String>,
+}
+impl<A, P, X> Debug for Dot<'_, A, P, X>
+where
+    A: Activator,
+    P: Propagator,
+    X: Phenotype,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dot")
+            .field("brain", self.brain)
+            .field("body", self.body)
+            .field("config", self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'b, A, P, X> Dot<'b, A, P, X>
+where
+    A: Activator,
+    P: Propagator,
+    X: Phenotype,
+{
+    /// `body` is expected to only reference neurons of `brain`.
+    pub fn new(brain: &'b Brain<A, P>, body: &'b Body<X>, config: &'b P::Config) -> Self {
+        Self { brain, body, config, neuron_label: None, connection_label: None }
+    }
+
+    /// Adds the activator gene to the label of every neuron.
+    pub fn with_neuron_labels(self) -> Self
+    where
+        A::Gene: Display,
+    {
+        Self { neuron_label: Some(|gene| gene.to_string()), ..self }
+    }
+
+    /// Adds the propagator gene to the label of every connection.
+    pub fn with_connection_labels(self) -> Self
+    where
+        P::Gene: Display,
+    {
+        Self { connection_label: Some(|gene| gene.to_string()), ..self }
+    }
+}
+impl<A, P, X> Display for Dot<'_, A, P, X>
+where
+    A: Activator,
+    P: Propagator,
+    X: Phenotype,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sensors = self.body.iter_sensor_neurons().collect::<HashSet<_>>();
+        let actions = self.body.iter_action_neurons().collect::<HashSet<_>>();
+        writeln!(f, "digraph brain {{")?;
+        writeln!(f, "    rankdir=LR;")?;
+        writeln!(f, "    node [shape=circle];")?;
+        for neuron in self.brain.neurons() {
+            let mut label = neuron.id.to_string();
+            if let Some(gene) = self.neuron_label.map(|label| label(&neuron.activator_gene)) {
+                label = format!("{label}\n{gene}");
+            }
+            let color = match (sensors.contains(&neuron.id), actions.contains(&neuron.id)) {
+                (true, true) => Some("plum"),
+                (true, false) => Some("lightblue"),
+                (false, true) => Some("lightsalmon"),
+                (false, false) => None,
+            };
+            write!(f, "    n{} [label=\"{}\"", neuron.id, escape(&label))?;
+            if let Some(color) = color {
+                write!(f, ", style=filled, fillcolor={color}")?;
+            }
+            writeln!(f, "];")?;
+        }
+        // NOTE: sensors and actions are kept at the borders of the graph
+        for (rank, ids) in [("source", &sensors), ("sink", &actions)] {
+            if ids.is_empty() {
+                continue;
+            }
+            write!(f, "    {{ rank={rank};")?;
+            for id in self.brain.neurons().iter().map(|neuron| neuron.id) {
+                if ids.contains(&id) {
+                    write!(f, " n{id};")?;
+                }
+            }
+            writeln!(f, " }}")?;
+        }
+        let propagator = P::default();
+        for conn in self.brain.connections() {
+            let mut attributes = Vec::new();
+            if let Some(gene) = self.connection_label.map(|label| label(&conn.propagator_gene)) {
+                attributes.push(format!("label=\"{}\"", escape(&gene)));
+            }
+            if conn.recurrent {
+                attributes.push("style=dashed, constraint=false".to_owned());
+            }
+            write!(f, "    n{} -> n{}", conn.from, conn.to)?;
+            if !attributes.is_empty() {
+                write!(f, " [{}]", attributes.join(", "))?;
+            }
+            writeln!(f, ";")?;
+            for modulator in propagator.modulation(&conn.propagator_gene, self.config) {
+                let modulator = *modulator.borrow();
+                writeln!(f, "    n{modulator} -> n{} [style=dotted, arrowhead=dot];", conn.to)?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Escapes a string to be used inside a quoted DOT identifier.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        numeric::{ActivationGene, Function, Modulated, ModulatedWeight},
+        test_util::TestPhenotype,
+    };
+
+    #[test]
+    fn dot_renders_roles_and_modulation() {
+        let mut brain = Brain::<crate::numeric::Activation, Modulated>::new();
+        let gene = ActivationGene { function: Function::Sigmoid, bias: 0.5 };
+        let ids = (0..3).map(|_| brain.add_neuron(gene.clone())).collect::<Vec<_>>();
+        let weight = |weight, modulator| ModulatedWeight { weight, modulator };
+        brain.add_connection(ids[0], ids[2], weight(0.5, Some(ids[1]))).unwrap();
+        brain.add_recurrent_connection(ids[2], ids[1], weight(2.0, None)).unwrap();
+        let body = Body::builder(TestPhenotype)
+            .sensor(ids[0], ())
+            .action(ids[2], ())
+            .build(brain.order())
+            .unwrap();
+
+        let plain = Dot::new(&brain, &body, &()).to_string();
+        assert!(plain.starts_with("digraph brain {"), "{plain}");
+        assert!(plain.contains(&format!("n{} [label=\"{}\"];", ids[1], ids[1])), "{plain}");
+        let sensor =
+            format!("n{} [label=\"{}\", style=filled, fillcolor=lightblue];", ids[0], ids[0]);
+        assert!(plain.contains(&sensor), "{plain}");
+        assert!(plain.contains("fillcolor=lightsalmon"), "{plain}");
+        assert!(plain.contains(&format!("n{} -> n{};", ids[0], ids[2])), "{plain}");
+        let modulation = format!("n{} -> n{} [style=dotted, arrowhead=dot];", ids[1], ids[2]);
+        assert!(plain.contains(&modulation), "{plain}");
+        let recurrent = format!("n{} -> n{} [style=dashed, constraint=false];", ids[2], ids[1]);
+        assert!(plain.contains(&recurrent), "{plain}");
+
+        let labeled =
+            Dot::new(&brain, &body, &()).with_neuron_labels().with_connection_labels().to_string();
+        assert!(labeled.contains(&format!("label=\"{}\\n{gene}\"", ids[1])), "{labeled}");
+        assert!(labeled.contains(&format!("[label=\"0.5 * {}\"]", ids[1])), "{labeled}");
+    }
+}
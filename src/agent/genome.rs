@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use super::*;
+use crate::random::Random;
 
 // A: 'static + for<'a> Activator<Input<'a> = C::Output<'a>, Output<'a> = P::Input<'a>>,
 // P: 'static + for<'p> Propagator<Output<'p> = C::Input<'p>>,
@@ -18,12 +19,21 @@ pub trait Genome: Debug + Clone {
         parents: impl IntoIterator<Item = (Self, Brain<Self::Activator, Self::Propagator>, Body<P>)>,
         parent_count: usize,
         children_count: usize,
+        rng: &mut Random,
         config: &Self::Config,
     ) -> impl Iterator<Item = (Self, Brain<Self::Activator, Self::Propagator>, Body<P>)>;
 
+    /// Returns a measure of how different two agents are, used to group agents into species.
+    fn distance<P: Phenotype>(
+        a: (&Self, &Brain<Self::Activator, Self::Propagator>, &Body<P>),
+        b: (&Self, &Brain<Self::Activator, Self::Propagator>, &Body<P>),
+        config: &Self::Config,
+    ) -> f64;
+
     fn spawn<'a, P, I>(
         parents: I,
         count: usize,
+        rng: &mut Random,
         config: &Self::Config,
     ) -> (Self, Brain<Self::Activator, Self::Propagator>, Body<P>)
     where
@@ -39,6 +39,21 @@ impl Display for NeuronID {
         write!(f, "ID:{}", self.into_inner())
     }
 }
+#[cfg(feature = "serde")]
+impl serde::Serialize for NeuronID {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.into_inner())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NeuronID {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = u32::deserialize(deserializer)?;
+        Self::try_from(id).ok_or_else(|| {
+            serde::de::Error::custom(format_args!("neuron id {id} is larger than {}", Self::MAX))
+        })
+    }
+}
 
 mod neuron_order {
     use std::collections::HashMap;
@@ -48,6 +63,7 @@ mod neuron_order {
     use super::*;
 
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct NeuronOrder(ThinVec<Option<NeuronID>>);
     impl NeuronOrder {
         pub fn new() -> Self {
@@ -158,7 +174,7 @@ mod neuron_order {
             let map = Self::build_mapping(order);
             self.0.clear();
             self.0.reserve(map.len());
-            self.0.extend(map.keys().copied().map(Some));
+            self.0.extend((0..map.len()).map(|i| NeuronID::try_from(i as u32)));
             map
         }
     }
@@ -176,6 +192,34 @@ impl Default for NeuronOrder {
     }
 }
 
+/// Snapshot of a [`NeuronOrder`] optimized for lookups during simulation.
+///
+/// Unused [`NeuronID`]s map to an index that is out of bounds for every buffer,
+/// so using the result to index into a slice will panic instead of returning a wrong value.
+#[derive(Debug, Clone, Default)]
+pub struct DenseOrder(thin_vec::ThinVec<u32>);
+impl DenseOrder {
+    /// # Panics
+    /// Panics if `neuron` is larger than every [`NeuronID`] used in the original order.
+    #[inline(always)]
+    pub fn index(&self, neuron: NeuronID) -> usize {
+        self.0[neuron.into_inner() as usize] as usize
+    }
+}
+impl From<&NeuronOrder> for DenseOrder {
+    fn from(order: &NeuronOrder) -> Self {
+        let mut indices = thin_vec::ThinVec::new();
+        for id in order.iter_used() {
+            let slot = id.into_inner() as usize;
+            if slot >= indices.len() {
+                indices.resize(slot + 1, u32::MAX);
+            }
+            indices[slot] = order.index(id).expect("used neurons should have an index") as u32;
+        }
+        Self(indices)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -249,4 +293,13 @@ mod test {
         }
         assert_eq!(std::cmp::Ordering::Equal, data.iter().map(|i| map[i]).cmp(order.iter_used()));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn neuron_id_rejects_reserved_values() {
+        let id = NeuronID::try_from(7).unwrap();
+        assert_eq!("7", serde_json::to_string(&id).unwrap());
+        assert_eq!(id, serde_json::from_str("7").unwrap());
+        assert!(serde_json::from_str::<NeuronID>(&u32::MAX.to_string()).is_err());
+    }
 }
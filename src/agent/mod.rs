@@ -1,20 +1,42 @@
+mod batch;
 mod body;
 mod brain;
 mod connection;
+mod dot;
 mod genome;
 mod index;
 mod neuron;
+mod plan;
 mod state;
 
+pub use batch::*;
 pub use body::*;
 pub use brain::*;
 pub use connection::*;
+pub use dot::*;
 pub use genome::*;
 pub use index::*;
 pub use neuron::*;
+pub use plan::*;
 pub use state::*;
 
-#[derive(Debug, Clone)]
+use crate::random::Random;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        bound(
+            serialize = "G: serde::Serialize, Brain<G::Activator, G::Propagator>: serde::Serialize, \
+                         Body<P>: serde::Serialize",
+            deserialize = "G: serde::Deserialize<'de>, \
+                           Brain<G::Activator, G::Propagator>: serde::Deserialize<'de>, \
+                           Body<P>: serde::Deserialize<'de>"
+        ),
+        try_from = "UncheckedAgent<G, P>"
+    )
+)]
 pub struct Agent<G, P>
 where
     G: Genome,
@@ -25,11 +47,58 @@ where
     genome: G,
 }
 
+/// Deserialized [`Agent`] before checking that `body` matches `brain`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound(deserialize = "G: serde::Deserialize<'de>, \
+                             Brain<G::Activator, G::Propagator>: serde::Deserialize<'de>, \
+                             Body<P>: serde::Deserialize<'de>"))]
+struct UncheckedAgent<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    brain:  Brain<G::Activator, G::Propagator>,
+    body:   Body<P>,
+    genome: G,
+}
+#[cfg(feature = "serde")]
+impl<G, P> TryFrom<UncheckedAgent<G, P>> for Agent<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    type Error = BodyError;
+
+    fn try_from(value: UncheckedAgent<G, P>) -> Result<Self, Self::Error> {
+        value.body.validate(value.brain.order())?;
+        Ok(Self { brain: value.brain, body: value.body, genome: value.genome })
+    }
+}
+impl<G, P> Clone for Agent<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn clone(&self) -> Self {
+        Self { brain: self.brain.clone(), body: self.body.clone(), genome: self.genome.clone() }
+    }
+}
+
 impl<G, P> Agent<G, P>
 where
     G: Genome,
     P: Phenotype,
 {
+    /// Creates an [`Agent`] from its parts, `body` is expected to only reference neurons of `brain`.
+    pub fn new(genome: G, brain: Brain<G::Activator, G::Propagator>, body: Body<P>) -> Self {
+        Self { brain, body, genome }
+    }
+
+    pub fn genome(&self) -> &G {
+        &self.genome
+    }
+
     pub fn brain(&self) -> &Brain<G::Activator, G::Propagator> {
         &self.brain
     }
@@ -38,6 +107,23 @@ where
         &self.body
     }
 
+    /// Renders the network of this agent, see [`Dot`].
+    pub fn dot<'a>(
+        &'a self,
+        config: &'a <G::Propagator as Propagator>::Config,
+    ) -> Dot<'a, G::Activator, G::Propagator, P> {
+        Dot::new(&self.brain, &self.body, config)
+    }
+
+    /// Returns a measure of how different two agents are (see [`Genome::distance`]).
+    pub fn distance(&self, other: &Self, config: &G::Config) -> f64 {
+        G::distance(
+            (&self.genome, &self.brain, &self.body),
+            (&other.genome, &other.brain, &other.body),
+            config,
+        )
+    }
+
     // TODO: add config
     /// # Safety
     /// Assumes that `parents` has at least `parent_count` elements.
@@ -45,12 +131,14 @@ where
         parents: impl IntoIterator<Item = Self>,
         parent_count: usize,
         children_count: usize,
+        rng: &mut Random,
         config: &G::Config,
     ) -> impl Iterator<Item = Self> {
         G::populate(
             parents.into_iter().map(|agent| (agent.genome, agent.brain, agent.body)),
             parent_count,
             children_count,
+            rng,
             config,
         )
         .map(|(genome, brain, body)| Self { genome, brain, body })
@@ -59,16 +147,22 @@ where
     pub fn populate(
         parents: impl IntoIterator<IntoIter: ExactSizeIterator, Item = Self>,
         count: usize,
+        rng: &mut Random,
         config: &G::Config,
     ) -> impl Iterator<Item = Self> {
         let parents = parents.into_iter();
         let parent_count = parents.size_hint().0;
-        unsafe { Self::populate_unchecked(parents, parent_count, count, config) }
+        unsafe { Self::populate_unchecked(parents, parent_count, count, rng, config) }
     }
 
     /// # Safety
     /// Assumes that `parents` has at least `parent_count` elements.
-    pub unsafe fn spawn_unchecked<'a, I>(parents: I, count: usize, config: &G::Config) -> Self
+    pub unsafe fn spawn_unchecked<'a, I>(
+        parents: I,
+        count: usize,
+        rng: &mut Random,
+        config: &G::Config,
+    ) -> Self
     where
         I: IntoIterator<Item = &'a Self>,
         G: 'a,
@@ -77,12 +171,13 @@ where
         let (genome, brain, body) = G::spawn(
             parents.into_iter().map(|agent| (&agent.genome, &agent.brain, &agent.body)),
             count,
+            rng,
             config,
         );
         Self { genome, brain, body }
     }
 
-    pub fn spawn<'a, I>(parents: I, config: &G::Config) -> Self
+    pub fn spawn<'a, I>(parents: I, rng: &mut Random, config: &G::Config) -> Self
     where
         I: IntoIterator<IntoIter: ExactSizeIterator, Item = &'a Self>,
         G: 'a,
@@ -90,6 +185,6 @@ where
     {
         let parents = parents.into_iter();
         let count = parents.size_hint().0;
-        unsafe { Self::spawn_unchecked(parents, count, config) }
+        unsafe { Self::spawn_unchecked(parents, count, rng, config) }
     }
 }
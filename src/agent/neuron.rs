@@ -24,11 +24,27 @@ pub trait Activator: Debug + Default {
 }
 
 /// Neuron data used both as static data during simulation and as a direct gene.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "A::Gene: serde::Serialize",
+        deserialize = "A::Gene: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Neuron<A: Activator> {
     pub id: NeuronID,
     pub activator_gene: A::Gene,
 }
+impl<A> Clone for Neuron<A>
+where
+    A: Activator,
+{
+    fn clone(&self) -> Self {
+        Self { id: self.id, activator_gene: self.activator_gene.clone() }
+    }
+}
 impl<A> Display for Neuron<A>
 where
     A: Activator<Gene: Display>,
@@ -0,0 +1,130 @@
+use thin_vec::ThinVec;
+
+use super::*;
+
+/// Single operation of a [`Plan`].
+/// All indices refer to positions in [`Brain::neurons`] and [`Brain::connections`].
+/// Every neuron has its own collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// Pushes the next input into the collector of a neuron.
+    Input(usize),
+    /// Propagates the output of neuron `from` through `connection` into the collector of `to`.
+    Propagate { connection: usize, from: usize, to: usize },
+    /// Activates a neuron with everything pushed into its collector.
+    Activate(usize),
+    /// Writes the output of a neuron to the next output.
+    Output(usize),
+}
+
+/// Flat list of [`Instruction`]s that performs a single simulation step of a [`Brain`]
+/// connected to a [`Body`].
+///
+/// All lookups are resolved once in [`Plan::compile`], so the plan can be reused for every step
+/// until the brain or body change.
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    instructions: ThinVec<Instruction>,
+    order:        DenseOrder,
+    fingerprint:  u64,
+    neurons:      usize,
+    connections:  usize,
+    inputs:       usize,
+    outputs:      usize,
+}
+
+impl Plan {
+    /// # Panics
+    /// Panics if `body` references neurons that are not part of `brain`.
+    pub fn compile<A, P, X>(brain: &Brain<A, P>, body: &Body<X>) -> Self
+    where
+        A: Activator,
+        P: Propagator,
+        X: Phenotype,
+    {
+        let order = brain.order();
+        let mut instructions = ThinVec::with_capacity(
+            2 * brain.neurons().len()
+                + brain.connections().len()
+                + body.sensor_count()
+                + body.action_count(),
+        );
+        let index = |id| order.index(id).expect("connections should only reference neurons");
+        // NOTE: recurrent connections read the outputs of the previous step,
+        // so they are propagated before any neuron is activated
+        for (connection, conn) in brain.connections().iter().enumerate() {
+            if conn.recurrent {
+                let (from, to) = (index(conn.from), index(conn.to));
+                instructions.push(Instruction::Propagate { connection, from, to });
+            }
+        }
+        // NOTE: sensors and actions are sorted by `order`, so they can be matched while iterating
+        let mut sensors = body.iter_sensor_neurons().peekable();
+        let mut actions = body.iter_action_neurons().peekable();
+        let mut connections = brain.connections().iter().enumerate().peekable();
+        for (to, neuron) in brain.neurons().iter().enumerate() {
+            if sensors.next_if_eq(&neuron.id).is_some() {
+                instructions.push(Instruction::Input(to));
+            }
+            while let Some((connection, conn)) = connections.next_if(|(_, c)| c.to == neuron.id) {
+                if !conn.recurrent {
+                    let from = index(conn.from);
+                    instructions.push(Instruction::Propagate { connection, from, to });
+                }
+            }
+            instructions.push(Instruction::Activate(to));
+            if actions.next_if_eq(&neuron.id).is_some() {
+                instructions.push(Instruction::Output(to));
+            }
+        }
+        assert!(
+            sensors.next().is_none() && actions.next().is_none(),
+            "all interface neurons should be included in the order"
+        );
+        Self {
+            instructions,
+            order: order.into(),
+            fingerprint: brain.fingerprint(),
+            neurons: brain.neurons().len(),
+            connections: brain.connections().len(),
+            inputs: body.sensor_count(),
+            outputs: body.action_count(),
+        }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn order(&self) -> &DenseOrder {
+        &self.order
+    }
+
+    /// Number of neurons of the compiled [`Brain`].
+    pub fn neuron_count(&self) -> usize {
+        self.neurons
+    }
+
+    /// Number of connections of the compiled [`Brain`].
+    pub fn connection_count(&self) -> usize {
+        self.connections
+    }
+
+    /// Number of inputs consumed by each step.
+    pub fn input_count(&self) -> usize {
+        self.inputs
+    }
+
+    /// Number of outputs written by each step.
+    pub fn output_count(&self) -> usize {
+        self.outputs
+    }
+
+    /// Checks if this plan was compiled for the topology of `brain`, see [`Brain::fingerprint`].
+    /// Changes of the body are not detected.
+    pub fn fits<A: Activator, P: Propagator>(&self, brain: &Brain<A, P>) -> bool {
+        self.neurons == brain.neurons().len()
+            && self.connections == brain.connections().len()
+            && self.fingerprint == brain.fingerprint()
+    }
+}
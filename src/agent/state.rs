@@ -5,6 +5,41 @@ use thin_vec::ThinVec;
 use super::*;
 use crate::arena::*;
 
+/// How many ticks [`State::settle`] runs for a single set of inputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Settle {
+    /// Always runs a fixed number of ticks (at least one).
+    Ticks(u32),
+    /// Runs until no output changes more than `epsilon` between two ticks,
+    /// but at most `max_ticks` ticks (at least one).
+    ///
+    /// A [`World`](crate::world::World) can only compare outputs after
+    /// [`World::with_convergence`](crate::world::World::with_convergence) was called,
+    /// otherwise it always runs `max_ticks` ticks.
+    Converge { epsilon: f64, max_ticks: u32 },
+}
+impl Default for Settle {
+    fn default() -> Self {
+        Self::Ticks(1)
+    }
+}
+
+/// Distance between two outputs used by [`Settle::Converge`].
+pub trait Difference {
+    fn difference(&self, other: &Self) -> f64;
+}
+impl Difference for f64 {
+    fn difference(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+}
+impl Difference for f32 {
+    fn difference(&self, other: &Self) -> f64 {
+        (self - other).abs() as f64
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Config<A, P, C>
 where
@@ -15,6 +50,7 @@ where
     pub activator:  A::Config,
     pub propagator: P::Config,
     pub collector:  C::Config,
+    pub settle:     Settle,
 }
 
 impl<A, P, C> Clone for Config<A, P, C>
@@ -28,20 +64,7 @@ where
             activator:  self.activator.clone(),
             propagator: self.propagator.clone(),
             collector:  self.collector.clone(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Interface {
-    Input(NeuronID),
-    Output(NeuronID),
-}
-impl Interface {
-    pub fn into_id(self) -> NeuronID {
-        match self {
-            Self::Input(id) => id,
-            Self::Output(id) => id,
+            settle:     self.settle,
         }
     }
 }
@@ -54,9 +77,11 @@ where
 {
     neuron_state:      Buffer<A>,
     connection_state:  Buffer<P>,
-    interface_order:   Buffer<Interface>,
+    /// One per neuron, so recurrent connections can be propagated ahead of their target.
+    collectors:        Buffer<C>,
+    plan:              Plan,
     modulation_buffer: ThinVec<P::Input<'static>>,
-    collector:         C,
+    ticks:             u32,
 }
 
 impl<A, P, C> Debug for State<A, P, C>
@@ -69,11 +94,42 @@ where
         f.debug_struct("State")
             .field("neuron_state", &self.neuron_state)
             .field("connection_state", &self.connection_state)
-            .field("interface_order", &self.interface_order)
-            .field("collector", &self.collector)
+            .field("collectors", &self.collectors)
+            .field("plan", &self.plan)
+            .field("ticks", &self.ticks)
             .finish_non_exhaustive()
     }
 }
+/// Serializes the same fields that are read by [`StateSnapshot`].
+#[cfg(feature = "serde")]
+impl<A, P, C> serde::Serialize for State<A, P, C>
+where
+    A: Activator + serde::Serialize,
+    P: 'static + Propagator + serde::Serialize,
+    C: Collector + serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("State", 3)?;
+        state.serialize_field("neurons", &self.neuron_state[..])?;
+        state.serialize_field("connections", &self.connection_state[..])?;
+        state.serialize_field("collectors", &self.collectors[..])?;
+        state.end()
+    }
+}
+
+/// Owned copy of a [`State`] that is not bound to an [`Arena`].
+/// Use [`State::from_snapshot`] to turn it back into a [`State`]
+/// or [`State::restore`] to load it into an existing one.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshot<A, P, C> {
+    neurons:     Vec<A>,
+    connections: Vec<P>,
+    collectors:  Vec<C>,
+}
+
 impl<A, P, C> State<A, P, C>
 where
     //  P -> C -> A -> P
@@ -84,45 +140,154 @@ where
     P: 'static + for<'p> Propagator<Output<'p> = C::Input<'p>>,
     C: Collector,
 {
-    pub fn create_for<X: Phenotype>(
+    /// # Panics
+    /// Panics if `body` does not match `brain`, use [`Body::builder`] to check this beforehand.
+    /// # Safety
+    /// The state has to be dropped, freed or moved into another arena
+    /// before `arena` is dropped or freed (see [`Arena::alloc_slice_with`]).
+    pub unsafe fn create_for<X: Phenotype>(
         brain: &Brain<A, P>,
         body: &Body<X>,
         arena: &mut Arena,
     ) -> Self {
-        let neuron_state = arena.alloc_slice_with(brain.neurons().len(), A::default);
-        let connection_state = arena.alloc_slice_with(brain.connections().len(), P::default);
-        // TODO: since both sensors and actions are sorted in itself, this can be replaced by an interleave_by
-        let mut interface_order = arena.alloc_slice_from_iter(
-            body.iter_sensor_neurons()
-                .map(Interface::Input)
-                .chain(body.iter_action_neurons().map(Interface::Output)),
-        );
-        interface_order.sort_by(|a, b| {
-            brain
-                .order()
-                .cmp(a.into_id(), b.into_id())
-                .expect("all interface neurons should be included in the order")
-        });
-        Self {
-            neuron_state,
-            connection_state,
-            interface_order,
-            modulation_buffer: ThinVec::new(),
-            collector: C::default(),
+        // SAFETY: guarantied by caller
+        unsafe {
+            Self {
+                neuron_state:      arena.alloc_slice_with(brain.neurons().len(), A::default),
+                connection_state:  arena.alloc_slice_with(brain.connections().len(), P::default),
+                collectors:        arena.alloc_slice_with(brain.neurons().len(), C::default),
+                plan:              Plan::compile(brain, body),
+                modulation_buffer: ThinVec::new(),
+                ticks:             0,
+            }
+        }
+    }
+
+    /// Recreates the [`State`] stored in `snapshot` for the same `brain` and `body`.
+    /// Returns `None` when `snapshot` does not match the size of `brain`.
+    /// # Safety
+    /// Same as [`State::create_for`].
+    pub unsafe fn from_snapshot<X: Phenotype>(
+        snapshot: StateSnapshot<A, P, C>,
+        brain: &Brain<A, P>,
+        body: &Body<X>,
+        arena: &mut Arena,
+    ) -> Option<Self> {
+        if snapshot.neurons.len() != brain.neurons().len()
+            || snapshot.connections.len() != brain.connections().len()
+            || snapshot.collectors.len() != brain.neurons().len()
+        {
+            return None;
+        }
+        // SAFETY: guarantied by caller
+        Some(unsafe {
+            Self {
+                neuron_state:      arena.alloc_slice_from_iter(snapshot.neurons),
+                connection_state:  arena.alloc_slice_from_iter(snapshot.connections),
+                collectors:        arena.alloc_slice_from_iter(snapshot.collectors),
+                plan:              Plan::compile(brain, body),
+                modulation_buffer: ThinVec::new(),
+                ticks:             0,
+            }
+        })
+    }
+
+    /// Sets all neurons, connections and collectors back to their default state
+    /// without allocating new buffers.
+    pub fn reset(&mut self) {
+        self.neuron_state.fill_with(A::default);
+        self.connection_state.fill_with(P::default);
+        self.collectors.fill_with(C::default);
+        self.ticks = 0;
+    }
+
+    /// Compiles a new [`Plan`] after the topology of `brain` or `body` changed without changing
+    /// the number of neurons and connections, e.g. when [`Brain::update_connection`] moved neurons.
+    /// The state is reset, since neurons and connections may have changed their positions.
+    /// Returns `None` and leaves the state untouched when `brain` does not match its size,
+    /// a new [`State`] has to be created in that case.
+    /// # Panics
+    /// Panics if `body` references neurons that are not part of `brain`.
+    pub fn recompile<X: Phenotype>(&mut self, brain: &Brain<A, P>, body: &Body<X>) -> Option<()> {
+        if brain.neurons().len() != self.neuron_state.len()
+            || brain.connections().len() != self.connection_state.len()
+        {
+            return None;
+        }
+        self.plan = Plan::compile(brain, body);
+        self.reset();
+        Some(())
+    }
+
+    /// Copies the current runtime state, so it can be restored later.
+    pub fn snapshot(&self) -> StateSnapshot<A, P, C>
+    where
+        A: Clone,
+        P: Clone,
+        C: Clone,
+    {
+        StateSnapshot {
+            neurons:     self.neuron_state.to_vec(),
+            connections: self.connection_state.to_vec(),
+            collectors:  self.collectors.to_vec(),
         }
     }
 
-    pub fn move_buffers(&mut self, arena: &mut Arena) {
-        // SAFETY: original buffers get overwritten so old pointers are inaccessible
+    /// Overwrites the runtime state with the content of `snapshot` without allocating new buffers.
+    /// Returns `None` and leaves the state untouched when `snapshot` does not match its size.
+    pub fn restore(&mut self, snapshot: &StateSnapshot<A, P, C>) -> Option<()>
+    where
+        A: Clone,
+        P: Clone,
+        C: Clone,
+    {
+        if snapshot.neurons.len() != self.neuron_state.len()
+            || snapshot.connections.len() != self.connection_state.len()
+            || snapshot.collectors.len() != self.collectors.len()
+        {
+            return None;
+        }
+        self.neuron_state.clone_from_slice(&snapshot.neurons);
+        self.connection_state.clone_from_slice(&snapshot.connections);
+        self.collectors.clone_from_slice(&snapshot.collectors);
+        self.ticks = 0;
+        Some(())
+    }
+
+    /// Number of ticks used by the last call to [`State::settle`].
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// The [`Plan`] used by [`State::step`], compiled when this state was created
+    /// or by [`State::recompile`].
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    /// Moves all buffers into `arena`,
+    /// e.g. to compact the states that are still alive before their old arena is freed.
+    /// # Safety
+    /// Same as [`State::create_for`], the state now belongs to `arena`.
+    pub unsafe fn move_buffers(&mut self, arena: &mut Arena) {
+        // SAFETY: guarantied by caller
         unsafe {
-            self.neuron_state = arena.move_into(&self.neuron_state);
-            self.connection_state = arena.move_into(&self.connection_state);
-            self.interface_order = arena.move_into(&self.interface_order);
+            arena.move_into(&mut self.neuron_state);
+            arena.move_into(&mut self.connection_state);
+            arena.move_into(&mut self.collectors);
         }
     }
 
-    fn get<'a>(neurons: &'a [A], order: &NeuronOrder, id: NeuronID) -> &'a A {
-        &neurons[order.index(id).expect("all neurons should be included in the order")]
+    /// Returns the memory of all buffers to `arena`, so it can be reused by other states.
+    /// # Safety
+    /// Assumes that the state was created in `arena` (or its buffers were moved into it).
+    pub unsafe fn free_buffers(self, arena: &mut Arena) {
+        // SAFETY: guarantied by caller
+        unsafe {
+            arena.free(self.neuron_state);
+            arena.free(self.connection_state);
+            arena.free(self.collectors);
+        }
     }
 
     #[inline(always)]
@@ -131,7 +296,7 @@ where
         edge: (&Connection<P>, &mut P),
         collector: &mut C,
         neurons: &[A],
-        order: &NeuronOrder,
+        order: &DenseOrder,
         buffer: &mut ThinVec<P::Input<'_>>,
         config: &Config<A, P, C>,
     ) {
@@ -144,7 +309,7 @@ where
         buffer.extend(
             edge.1
                 .modulation(&edge.0.propagator_gene, &config.propagator)
-                .map(|id| Self::get(neurons, order, *id.borrow()).output()),
+                .map(|id| neurons[order.index(*id.borrow())].output()),
         );
         let edge = edge.1.propagate(input, buffer, &edge.0.propagator_gene, &config.propagator);
         collector.push(edge, &config.collector);
@@ -159,6 +324,11 @@ where
         collector.clear(&config.collector);
     }
 
+    /// Advances the simulation by a single step, activating every neuron once in topological order.
+    ///
+    /// Recurrent connections are propagated before any neuron is activated,
+    /// so the state of every neuron acts as a one step delay buffer:
+    /// they always receive the output of the previous step (the default state during the first step).
     pub fn step<I, O>(
         &mut self,
         brain: &Brain<A, P>,
@@ -169,36 +339,128 @@ where
         for<'c> &'c I: Into<C::Input<'c>>,
         O: for<'a> From<A::Output<'a>>,
     {
+        self.tick(brain, inputs, outputs, config, |output, value| *output = value);
+    }
+
+    /// Repeats [`State::step`] with the same inputs as configured by [`Config::settle`].
+    /// Returns the number of ticks, which is also available from [`State::ticks`].
+    pub fn settle<I, O>(
+        &mut self,
+        brain: &Brain<A, P>,
+        inputs: &[I],
+        outputs: &mut [O],
+        config: &Config<A, P, C>,
+    ) -> u32
+    where
+        for<'c> &'c I: Into<C::Input<'c>>,
+        O: Difference + for<'a> From<A::Output<'a>>,
+    {
+        self.settle_by(brain, inputs, outputs, config, O::difference)
+    }
+
+    /// Same as [`State::settle`], but uses `difference` instead of [`Difference`]
+    /// to compare outputs for [`Settle::Converge`].
+    pub fn settle_by<I, O>(
+        &mut self,
+        brain: &Brain<A, P>,
+        inputs: &[I],
+        outputs: &mut [O],
+        config: &Config<A, P, C>,
+        difference: impl Fn(&O, &O) -> f64,
+    ) -> u32
+    where
+        for<'c> &'c I: Into<C::Input<'c>>,
+        O: for<'a> From<A::Output<'a>>,
+    {
+        self.ticks = match config.settle {
+            Settle::Ticks(ticks) => {
+                let ticks = ticks.max(1);
+                for _ in 0..ticks {
+                    self.step(brain, inputs, outputs, config);
+                }
+                ticks
+            },
+            Settle::Converge { epsilon, max_ticks } => {
+                // NOTE: the first tick is not compared, since `outputs` can contain anything
+                self.step(brain, inputs, outputs, config);
+                let mut ticks = 1;
+                while ticks < max_ticks {
+                    let mut change = 0.0f64;
+                    self.tick(brain, inputs, outputs, config, |output, value| {
+                        change = change.max(difference(&value, output));
+                        *output = value;
+                    });
+                    ticks += 1;
+                    if change <= epsilon {
+                        break;
+                    }
+                }
+                ticks
+            },
+        };
+        self.ticks
+    }
+
+    #[inline(always)]
+    fn tick<I, O>(
+        &mut self,
+        brain: &Brain<A, P>,
+        inputs: &[I],
+        outputs: &mut [O],
+        config: &Config<A, P, C>,
+        mut write: impl FnMut(&mut O, O),
+    ) where
+        for<'c> &'c I: Into<C::Input<'c>>,
+        O: for<'a> From<A::Output<'a>>,
+    {
+        assert!(self.plan.fits(brain), "brain should match the compiled plan");
+        assert!(inputs.len() >= self.plan.input_count(), "input buffer is not big enough");
+        assert!(outputs.len() >= self.plan.output_count(), "output buffer is not big enough");
         let mut inputs = inputs.iter();
         let mut outputs = outputs.iter_mut();
-        let mut interface = self.interface_order.iter().peekable();
-        let mut connections =
-            brain.connections().iter().zip(self.connection_state.iter_mut()).peekable();
-        for (index, neuron) in brain.neurons().iter().enumerate() {
-            if interface.next_if(|i| **i == Interface::Input(neuron.id)).is_some() {
-                self.collector.push(
-                    inputs.next().expect("input buffer is not big enough").into(),
-                    &config.collector,
-                );
-            }
-            while let Some(edge) = connections.next_if(|(conn, _)| conn.to == neuron.id) {
-                let state = Self::get(&self.neuron_state, brain.order(), edge.0.from);
-                Self::push(
-                    state,
-                    edge,
-                    &mut self.collector,
-                    &self.neuron_state,
-                    brain.order(),
-                    &mut self.modulation_buffer,
-                    config,
-                );
-            }
-            // SAFETY: Since `neuron_state` and `brain.neurons()` are always the same length
-            // indexing into `neuron_state` with an index received from enumerating `brain.neurons()` is always safe.
-            let state = unsafe { self.neuron_state.get_unchecked_mut(index) };
-            Self::activate(&mut self.collector, (neuron, state), config);
-            if interface.next_if(|o| **o == Interface::Output(neuron.id)).is_some() {
-                *outputs.next().expect("output buffer is not big enough") = state.output().into();
+        // SAFETY: All indices of the plan are inbounds for a brain that fits the plan
+        // and all state buffers always have the same length as the brain.
+        // The plan contains one `Input` and `Output` for every sensor and action respectively,
+        // so the size of both buffers was already checked above.
+        for instruction in self.plan.instructions() {
+            match *instruction {
+                Instruction::Input(index) => {
+                    let input = unsafe { inputs.next().unwrap_unchecked() };
+                    let collector = unsafe { self.collectors.get_unchecked_mut(index) };
+                    collector.push(input.into(), &config.collector);
+                },
+                Instruction::Propagate { connection, from, to } => {
+                    let edge = unsafe {
+                        (
+                            brain.connections().get_unchecked(connection),
+                            self.connection_state.get_unchecked_mut(connection),
+                        )
+                    };
+                    Self::push(
+                        unsafe { self.neuron_state.get_unchecked(from) },
+                        edge,
+                        unsafe { self.collectors.get_unchecked_mut(to) },
+                        &self.neuron_state,
+                        self.plan.order(),
+                        &mut self.modulation_buffer,
+                        config,
+                    );
+                },
+                Instruction::Activate(index) => {
+                    let neuron = unsafe {
+                        (
+                            brain.neurons().get_unchecked(index),
+                            self.neuron_state.get_unchecked_mut(index),
+                        )
+                    };
+                    let collector = unsafe { self.collectors.get_unchecked_mut(index) };
+                    Self::activate(collector, neuron, config);
+                },
+                Instruction::Output(index) => {
+                    let output = unsafe { outputs.next().unwrap_unchecked() };
+                    let state = unsafe { self.neuron_state.get_unchecked(index) };
+                    write(output, state.output().into());
+                },
             }
         }
     }
@@ -213,7 +475,12 @@ mod test {
         kind:  SignalKind,
         value: f64,
     }
-    #[derive(Debug, Default)]
+    impl From<&f64> for Signal {
+        fn from(value: &f64) -> Self {
+            Self { kind: SignalKind::Data, value: *value }
+        }
+    }
+    #[derive(Debug, Clone, Default)]
     struct Cumulant {
         pub data:    f64,
         pub control: f64,
@@ -226,7 +493,7 @@ mod test {
     struct NeuronConfig {
         pub activation_threshold: f64,
     }
-    #[derive(Debug, Default)]
+    #[derive(Debug, Clone, Default)]
     struct TestActivator {
         pub state: f64,
     }
@@ -271,7 +538,7 @@ mod test {
     }
     #[derive(Debug, Default)]
     struct ConnectionConfig;
-    #[derive(Debug, Default)]
+    #[derive(Debug, Clone, Default)]
     struct TestPropagator;
     impl Propagator for TestPropagator {
         type Config = ConnectionConfig;
@@ -297,6 +564,14 @@ mod test {
             .into_iter()
         }
 
+        fn referenced_neurons(gene: &Self::Gene) -> impl Iterator<Item: Borrow<NeuronID>> {
+            match gene.weight {
+                Weight::Direct(_) => None,
+                Weight::Modulated(id) => Some(id),
+            }
+            .into_iter()
+        }
+
         fn propagate(
             &mut self,
             input: Self::Input<'_>,
@@ -311,7 +586,7 @@ mod test {
             Signal { kind: gene.kind, value }
         }
     }
-    #[derive(Debug, Default)]
+    #[derive(Debug, Clone, Default)]
     struct TestCollector {
         state: Cumulant,
     }
@@ -342,6 +617,7 @@ mod test {
             self.state.control = 0.0;
         }
     }
+    #[derive(Debug, Clone)]
     struct TestPhenotype;
     impl Phenotype for TestPhenotype {
         type ActionGene = ();
@@ -350,37 +626,16 @@ mod test {
     type TestBrain = Brain<TestActivator, TestPropagator>;
     type TestBody = Body<TestPhenotype>;
     type TestConfig = Config<TestActivator, TestPropagator, TestCollector>;
+    type TestState = State<TestActivator, TestPropagator, TestCollector>;
 
     fn run(brain: &TestBrain, body: &TestBody, config: &TestConfig, inputs: &[f64]) -> Vec<f64> {
         let mut arena = Arena::new();
-        // FIXME: trait bounds not satisfied
-        // apparently Signal != Signal and &'i Cumulant != &'o Cumulant?
-        let mut state = State::create_for(brain, body, &mut arena);
+        let mut state = unsafe { TestState::create_for(brain, body, &mut arena) };
         let mut outputs = vec![0.0; body.action_count()];
         state.step(brain, inputs, &mut outputs, config);
         outputs
     }
 
-    fn free_ids(order: &NeuronOrder, count: usize) -> Vec<NeuronID> {
-        let mut result = Vec::new();
-        if count == 0 {
-            return result;
-        }
-        let Some(mut current) = order.next_free(None) else { return result };
-        result.push(current);
-        if count == 1 {
-            return result;
-        }
-        while let Some(next) = order.next_free(Some(current)) {
-            result.push(next);
-            if result.len() == count {
-                return result;
-            }
-            current = next;
-        }
-        result
-    }
-
     #[test]
     fn xor() {
         // inputs: <0>, <1>
@@ -388,54 +643,235 @@ mod test {
         // <3> = <0> + <1> if -1.0 * <2>
         // outputs: <3>
         let mut brain = TestBrain::new();
-        // TODO: construct body
-        let mut body = TestBody::new();
-        {
-            let mut access = brain.raw();
-            let ids = free_ids(&access.order, 4);
-            access.neurons.extend(
-                ids.iter()
-                    .copied()
-                    .map(|id| Neuron { id, activator_gene: NeuronGene { speed: 1.0 } }),
-            );
-            access.connections.push(Connection {
-                from: ids[0],
-                to: ids[2],
-                propagator_gene: ConnectionGene {
-                    kind:   SignalKind::Data,
-                    weight: Weight::Modulated(ids[1]),
-                },
-            });
-            access.connections.push(Connection {
-                from: ids[0],
-                to: ids[3],
-                propagator_gene: ConnectionGene {
-                    kind:   SignalKind::Data,
-                    weight: Weight::Direct(1.0),
-                },
-            });
-            access.connections.push(Connection {
-                from: ids[1],
-                to: ids[3],
-                propagator_gene: ConnectionGene {
-                    kind:   SignalKind::Data,
-                    weight: Weight::Direct(1.0),
-                },
-            });
-            access.connections.push(Connection {
-                from: ids[2],
-                to: ids[3],
-                propagator_gene: ConnectionGene {
-                    kind:   SignalKind::Control,
-                    weight: Weight::Direct(-1.0),
-                },
-            });
-            access.inputs.extend(ids.into_iter().take(2));
-        }
+        let ids = (0..4)
+            .map(|_| brain.add_neuron(NeuronGene { speed: 1.0 }))
+            .collect::<Vec<_>>();
+        brain
+            .add_connection(ids[0], ids[2], ConnectionGene {
+                kind:   SignalKind::Data,
+                weight: Weight::Modulated(ids[1]),
+            })
+            .unwrap();
+        brain
+            .add_connection(ids[0], ids[3], ConnectionGene {
+                kind:   SignalKind::Data,
+                weight: Weight::Direct(1.0),
+            })
+            .unwrap();
+        brain
+            .add_connection(ids[1], ids[3], ConnectionGene {
+                kind:   SignalKind::Data,
+                weight: Weight::Direct(1.0),
+            })
+            .unwrap();
+        brain
+            .add_connection(ids[2], ids[3], ConnectionGene {
+                kind:   SignalKind::Control,
+                weight: Weight::Direct(-1.0),
+            })
+            .unwrap();
+        let body = TestBody::builder(TestPhenotype)
+            .sensor(ids[1], ())
+            .sensor(ids[0], ())
+            .action(ids[3], ())
+            .build(brain.order())
+            .unwrap();
         let config = TestConfig::default();
         assert_eq!(run(&brain, &body, &config, &[0.0, 0.0]), vec![0.0]);
         assert_eq!(run(&brain, &body, &config, &[1.0, 0.0]), vec![1.0]);
         assert_eq!(run(&brain, &body, &config, &[0.0, 1.0]), vec![1.0]);
         assert_eq!(run(&brain, &body, &config, &[1.0, 1.0]), vec![0.0]);
     }
+
+    #[test]
+    fn recurrent_connections_are_delayed() {
+        // inputs: <0>
+        // <1> = <0> + previous <1>
+        // outputs: <1>
+        let mut brain = TestBrain::new();
+        let ids = (0..2)
+            .map(|_| brain.add_neuron(NeuronGene { speed: 1.0 }))
+            .collect::<Vec<_>>();
+        let gene = ConnectionGene { kind: SignalKind::Data, weight: Weight::Direct(1.0) };
+        brain.add_connection(ids[0], ids[1], gene.clone()).unwrap();
+        brain.add_recurrent_connection(ids[1], ids[1], gene).unwrap();
+        // SAFETY: sensors and actions are listed in order
+        let body = unsafe {
+            TestBody::new_unchecked(
+                [Sensor { neuron: ids[0], gene: () }].into_iter().collect(),
+                [Action { neuron: ids[1], gene: () }].into_iter().collect(),
+                TestPhenotype,
+            )
+        };
+        let config = TestConfig::default();
+        let mut arena = Arena::new();
+        let mut state = unsafe { TestState::create_for(&brain, &body, &mut arena) };
+        let mut outputs = [0.0];
+        for expected in [1.0, 2.0, 3.0] {
+            state.step(&brain, &[1.0], &mut outputs, &config);
+            assert_eq!([expected], outputs);
+        }
+    }
+
+    #[test]
+    fn settle_repeats_steps() {
+        // inputs: <0>
+        // <1> approaches <0> by halving the distance every tick
+        // outputs: <1>
+        let mut brain = TestBrain::new();
+        let ids = [1.0, 0.5].map(|speed| brain.add_neuron(NeuronGene { speed }));
+        let gene = ConnectionGene { kind: SignalKind::Data, weight: Weight::Direct(1.0) };
+        brain.add_connection(ids[0], ids[1], gene).unwrap();
+        // SAFETY: sensors and actions are listed in order
+        let body = unsafe {
+            TestBody::new_unchecked(
+                [Sensor { neuron: ids[0], gene: () }].into_iter().collect(),
+                [Action { neuron: ids[1], gene: () }].into_iter().collect(),
+                TestPhenotype,
+            )
+        };
+        let mut arena = Arena::new();
+        let mut settle = |settle| {
+            let config = TestConfig { settle, ..Default::default() };
+            let mut state = unsafe { TestState::create_for(&brain, &body, &mut arena) };
+            let mut outputs = [0.0];
+            let ticks = state.settle(&brain, &[1.0], &mut outputs, &config);
+            assert_eq!(ticks, state.ticks());
+            (ticks, outputs[0])
+        };
+        assert_eq!((1, 0.5), settle(Settle::default()));
+        assert_eq!((3, 0.875), settle(Settle::Ticks(3)));
+        assert_eq!((4, 0.9375), settle(Settle::Converge { epsilon: 0.1, max_ticks: 10 }));
+        assert_eq!((3, 0.875), settle(Settle::Converge { epsilon: 0.1, max_ticks: 3 }));
+    }
+
+    #[test]
+    fn plan_follows_topological_order() {
+        // inputs: <0>
+        // <1> = <0> + previous <1>
+        // outputs: <1>
+        let mut brain = TestBrain::new();
+        let ids = (0..2)
+            .map(|_| brain.add_neuron(NeuronGene { speed: 1.0 }))
+            .collect::<Vec<_>>();
+        let gene = ConnectionGene { kind: SignalKind::Data, weight: Weight::Direct(1.0) };
+        brain.add_recurrent_connection(ids[1], ids[1], gene.clone()).unwrap();
+        brain.add_connection(ids[0], ids[1], gene).unwrap();
+        // SAFETY: sensors and actions are listed in order
+        let body = unsafe {
+            TestBody::new_unchecked(
+                [Sensor { neuron: ids[0], gene: () }].into_iter().collect(),
+                [Action { neuron: ids[1], gene: () }].into_iter().collect(),
+                TestPhenotype,
+            )
+        };
+        let plan = Plan::compile(&brain, &body);
+        let propagate = |from: usize| {
+            let connection = brain.find_connection(ids[from], ids[1]).unwrap();
+            Instruction::Propagate { connection, from, to: 1 }
+        };
+        let expected = [
+            propagate(1),
+            Instruction::Input(0),
+            Instruction::Activate(0),
+            propagate(0),
+            Instruction::Activate(1),
+            Instruction::Output(1),
+        ];
+        assert_eq!(expected, plan.instructions());
+        assert!(plan.fits(&brain));
+        brain.add_neuron(NeuronGene { speed: 1.0 });
+        assert!(!plan.fits(&brain));
+    }
+
+    #[test]
+    fn recompile_follows_topology_changes() {
+        // inputs: <0>
+        // <1> = <0> + previous <1>
+        // outputs: <1>
+        let mut brain = TestBrain::new();
+        let ids = (0..2)
+            .map(|_| brain.add_neuron(NeuronGene { speed: 1.0 }))
+            .collect::<Vec<_>>();
+        let gene = ConnectionGene { kind: SignalKind::Data, weight: Weight::Direct(1.0) };
+        brain.add_connection(ids[0], ids[1], gene.clone()).unwrap();
+        brain.add_recurrent_connection(ids[1], ids[1], gene.clone()).unwrap();
+        // SAFETY: sensors and actions are listed in order
+        let body = unsafe {
+            TestBody::new_unchecked(
+                [Sensor { neuron: ids[0], gene: () }].into_iter().collect(),
+                [Action { neuron: ids[1], gene: () }].into_iter().collect(),
+                TestPhenotype,
+            )
+        };
+        let mut arena = Arena::new();
+        let mut state = unsafe { TestState::create_for(&brain, &body, &mut arena) };
+        let config = TestConfig::default();
+        let mut outputs = [0.0];
+
+        // NOTE: genes are not part of the topology, the last connection also keeps its index
+        let index = brain.find_connection(ids[1], ids[1]).unwrap();
+        brain.update_connection(index, |gene| gene.weight = Weight::Direct(2.0)).unwrap();
+        assert!(state.plan().fits(&brain));
+
+        // same size, but <1> feeds back into <0> instead of itself
+        brain.remove_connection(ids[1], ids[1]).unwrap();
+        brain.add_recurrent_connection(ids[1], ids[0], gene).unwrap();
+        assert!(!state.plan().fits(&brain));
+        assert_eq!(Some(()), state.recompile(&brain, &body));
+        assert!(state.plan().fits(&brain));
+        assert_eq!(1, state.settle(&brain, &[1.0], &mut outputs, &config));
+
+        brain.add_neuron(NeuronGene { speed: 1.0 });
+        assert_eq!(None, state.recompile(&brain, &body));
+        assert!(!state.plan().fits(&brain));
+    }
+
+    #[test]
+    fn reset_and_restore_rewind_state() {
+        // inputs: <0>
+        // <1> = <0> + previous <1>
+        // outputs: <1>
+        let mut brain = TestBrain::new();
+        let ids = (0..2)
+            .map(|_| brain.add_neuron(NeuronGene { speed: 1.0 }))
+            .collect::<Vec<_>>();
+        let gene = ConnectionGene { kind: SignalKind::Data, weight: Weight::Direct(1.0) };
+        brain.add_connection(ids[0], ids[1], gene.clone()).unwrap();
+        brain.add_recurrent_connection(ids[1], ids[1], gene).unwrap();
+        // SAFETY: sensors and actions are listed in order
+        let body = unsafe {
+            TestBody::new_unchecked(
+                [Sensor { neuron: ids[0], gene: () }].into_iter().collect(),
+                [Action { neuron: ids[1], gene: () }].into_iter().collect(),
+                TestPhenotype,
+            )
+        };
+        let config = TestConfig::default();
+        let mut arena = Arena::new();
+        let mut state = unsafe { TestState::create_for(&brain, &body, &mut arena) };
+        let mut outputs = [0.0];
+        let mut step = |state: &mut TestState| {
+            state.step(&brain, &[1.0], &mut outputs, &config);
+            outputs[0]
+        };
+        step(&mut state);
+        step(&mut state);
+        let snapshot = state.snapshot();
+        assert_eq!(3.0, step(&mut state));
+        assert_eq!(4.0, step(&mut state));
+        state.restore(&snapshot).unwrap();
+        assert_eq!(3.0, step(&mut state));
+        state.reset();
+        assert_eq!(1.0, step(&mut state));
+
+        let mut other = unsafe { TestState::create_for(&brain, &body, &mut arena) };
+        other.restore(&snapshot).unwrap();
+        assert_eq!(3.0, step(&mut other));
+        // SAFETY: there are no sensors and actions
+        let empty =
+            unsafe { TestBody::new_unchecked(ThinVec::new(), ThinVec::new(), TestPhenotype) };
+        let mut small = unsafe { TestState::create_for(&TestBrain::new(), &empty, &mut arena) };
+        assert!(small.restore(&snapshot).is_none());
+    }
 }
@@ -1,92 +1,558 @@
 use std::{
-    mem::{self, ManuallyDrop},
-    ptr, slice,
+    alloc::{self, Layout},
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
 };
+#[cfg(debug_assertions)]
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+/// Size of the first chunk allocated by an [`Arena`], following chunks double in size.
+const CHUNK_SIZE: usize = 4096;
+/// Minimum alignment of all chunks.
+const CHUNK_ALIGN: usize = 16;
+
+/// Tracks how often an [`Arena`] was freed to detect use after free in debug builds.
+#[cfg(debug_assertions)]
+#[derive(Debug, Clone)]
+struct Guard {
+    epoch:   Arc<AtomicU64>,
+    created: u64,
+}
+#[cfg(debug_assertions)]
+impl Guard {
+    fn check(&self) {
+        assert_eq!(
+            self.created,
+            self.epoch.load(Ordering::Relaxed),
+            "buffer was used after its arena was freed"
+        );
+    }
+}
+
+/// Slice of values living inside an [`Arena`].
+///
+/// The values are dropped together with the [`Buffer`], while the memory is only reclaimed
+/// by [`Arena::free_all`] or when the [`Arena`] is dropped, so every [`Buffer`] has to be dropped
+/// or moved before that. Buffers are not tied to the lifetime of their arena
+/// (so both can be stored next to each other), which is why allocating them is `unsafe`.
+/// In debug builds every access checks that the [`Arena`] was not freed yet.
+pub struct Buffer<T> {
+    ptr:    NonNull<T>,
+    len:    usize,
+    #[cfg(debug_assertions)]
+    guard:  Guard,
+    marker: PhantomData<T>,
+}
+// SAFETY: a buffer owns its values exclusively (like `Box<[T]>`), the arena never touches them
+// after handing out the buffer, and the guard is only shared through atomics
+unsafe impl<T: Send> Send for Buffer<T> {}
+// SAFETY: shared references to a buffer only give out shared references to its values
+unsafe impl<T: Sync> Sync for Buffer<T> {}
+impl<T> Buffer<T> {
+    #[inline(always)]
+    fn check(&self) {
+        #[cfg(debug_assertions)]
+        self.guard.check();
+    }
+}
+impl<T> Deref for Buffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.check();
+        // SAFETY: `ptr` points to `len` initialized values that live as long as the arena
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+impl<T> DerefMut for Buffer<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.check();
+        // SAFETY: `ptr` points to `len` initialized values that are only reachable through `self`
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        if !mem::needs_drop::<T>() {
+            return;
+        }
+        self.check();
+        // SAFETY: all values are initialized and never used again
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len)) };
+    }
+}
+impl<T: Debug> Debug for Buffer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
 
-use thin_slice::ThinBoxedSlice;
+/// Continuous block of memory owned by an [`Arena`].
+#[derive(Debug)]
+struct Chunk {
+    ptr:    NonNull<u8>,
+    layout: Layout,
+    used:   usize,
+}
+impl Chunk {
+    fn new(layout: Layout) -> Self {
+        // SAFETY: `layout` always has a non-zero size
+        let ptr = unsafe { alloc::alloc(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Self { ptr, layout, used: 0 }
+    }
 
-pub type Buffer<T> = ManuallyDrop<ThinBoxedSlice<T>>;
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let start = self.ptr.as_ptr().addr();
+        (start..start + self.layout.size()).contains(&ptr.as_ptr().addr())
+    }
 
-/// # Safety
-/// Assumes that `src` is a valid aligned pointer to values of type `B`.
-unsafe fn cast_slice<A, B>(src: &mut [A]) -> &mut [B] {
-    let new_len = if size_of::<A>() == size_of::<B>() {
-        src.len()
-    } else {
-        mem::size_of_val(src) / size_of::<B>()
-    };
-    // SAFETY: constraits should be validated at callsite
-    unsafe { slice::from_raw_parts_mut(src.as_mut_ptr() as *mut B, new_len) }
+    /// Returns a pointer to unused memory fitting `layout` or `None` when the chunk is full.
+    fn try_alloc(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+        let padding = self.ptr.as_ptr().wrapping_add(self.used).align_offset(layout.align());
+        let start = self.used + padding;
+        let end = start.checked_add(layout.size())?;
+        if end > self.layout.size() {
+            return None;
+        }
+        self.used = end;
+        // SAFETY: `start` is inside the chunk, so the pointer is non-null
+        Some(unsafe { NonNull::new_unchecked(self.ptr.as_ptr().add(start)) })
+    }
 }
 
-// TODO: validate that casting from slice -> box is ok
+/// Bump allocator handing out [`Buffer`]s.
+///
+/// Memory is allocated in chunks that are never moved, so all [`Buffer`]s stay at a stable address
+/// until [`Arena::free_all`] is called.
+/// Single buffers can be returned using [`Arena::free`] to reuse their memory in later allocations.
+/// Adjacent free blocks are merged, but buffers are never moved to close the gaps between them.
 #[derive(Debug, Default)]
-pub struct Arena(Vec<u8>);
+pub struct Arena {
+    chunks:  Vec<Chunk>,
+    /// Index of the first chunk that may still have free space.
+    current: usize,
+    /// Free blocks by their address, mapping to their size in bytes.
+    /// Blocks are never adjacent to each other or to the unused end of their chunk.
+    free:    BTreeMap<NonNull<u8>, usize>,
+    /// Same blocks as `free` sorted by their size in bytes.
+    by_size: BTreeSet<(usize, NonNull<u8>)>,
+    #[cfg(debug_assertions)]
+    epoch:   Arc<AtomicU64>,
+}
+// SAFETY: chunks and free blocks are owned exclusively by the arena and only accessed
+// through `&mut self`, values inside of them are owned by their `Buffer`s instead
+unsafe impl Send for Arena {}
 impl Arena {
     pub fn new() -> Self {
         Self::default()
     }
 
-    fn next_aligned<T>(&self) -> usize {
-        // FIXME: this will leave a align_of::<T> sized gap when ptr is aligned
-        self.0.len() + align_of::<T>() - self.0.as_ptr() as usize % align_of::<T>()
+    /// Takes the smallest free block that fits `layout` and returns the rest to the free list.
+    fn reuse(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+        let (size, ptr) = self
+            .by_size
+            .range((layout.size(), NonNull::dangling())..)
+            .find(|(_, ptr)| ptr.as_ptr().align_offset(layout.align()) == 0)
+            .copied()?;
+        self.free.remove(&ptr);
+        self.by_size.remove(&(size, ptr));
+        if size > layout.size() {
+            // SAFETY: the rest is still inside the free block
+            let rest = unsafe { ptr.add(layout.size()) };
+            // NOTE: the neighbours of the block were not free, so the rest can't be merged either
+            self.free.insert(rest, size - layout.size());
+            self.by_size.insert((size - layout.size(), rest));
+        }
+        Some(ptr)
+    }
+
+    /// Makes `size` bytes at `ptr` available for later allocations,
+    /// merging them with adjacent free blocks of the same chunk.
+    fn release(&mut self, mut ptr: NonNull<u8>, mut size: usize) {
+        let index = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.contains(ptr))
+            .expect("released memory should belong to a chunk");
+        let chunk = &mut self.chunks[index];
+        if let Some((&prev, &prev_size)) = self.free.range(..ptr).next_back() {
+            if chunk.contains(prev) && prev.as_ptr().wrapping_add(prev_size) == ptr.as_ptr() {
+                self.free.remove(&prev);
+                self.by_size.remove(&(prev_size, prev));
+                (ptr, size) = (prev, prev_size + size);
+            }
+        }
+        // SAFETY: the block ends inside of the chunk (or right behind it)
+        let next = unsafe { ptr.add(size) };
+        if let Some(next_size) = self.free.get(&next).copied().filter(|_| chunk.contains(next)) {
+            self.free.remove(&next);
+            self.by_size.remove(&(next_size, next));
+            size += next_size;
+        }
+        let offset = ptr.as_ptr().addr() - chunk.ptr.as_ptr().addr();
+        if offset + size == chunk.used {
+            chunk.used = offset;
+            self.current = self.current.min(index);
+        } else {
+            self.free.insert(ptr, size);
+            self.by_size.insert((size, ptr));
+        }
+    }
+
+    fn alloc_layout(&mut self, layout: Layout) -> NonNull<u8> {
+        if let Some(ptr) = self.reuse(layout) {
+            return ptr;
+        }
+        while let Some(chunk) = self.chunks.get_mut(self.current) {
+            if let Some(ptr) = chunk.try_alloc(layout) {
+                return ptr;
+            }
+            self.current += 1;
+        }
+        let size = self.chunks.last().map_or(CHUNK_SIZE, |chunk| chunk.layout.size() * 2);
+        let align = layout.align().max(CHUNK_ALIGN);
+        let chunk = Layout::from_size_align(size.max(layout.size()), align)
+            .expect("chunk size should not overflow");
+        self.chunks.push(Chunk::new(chunk));
+        self.current = self.chunks.len() - 1;
+        self.chunks[self.current].try_alloc(layout).expect("new chunk should fit the allocation")
     }
 
-    pub fn alloc_slice_with<T>(&mut self, size: usize, default: impl Fn() -> T) -> Buffer<T> {
-        let len = size_of::<T>() * size;
-        // TODO: handle zero sized types
-        let start = self.next_aligned::<T>();
-        self.0.resize(start + len, 0);
-        // SAFETY: results from `next_aligned` will always return aligned values
-        let result = unsafe { cast_slice(&mut self.0[start..]) };
-        result.iter_mut().for_each(|x| unsafe { ptr::write(x, default()) });
-        unsafe { ManuallyDrop::new(ThinBoxedSlice::from_raw(result as *mut _)) }
+    /// Allocates space for `len` values without initializing them.
+    fn alloc_uninit<T>(&mut self, len: usize) -> NonNull<T> {
+        if size_of::<T>() == 0 || len == 0 {
+            return NonNull::dangling();
+        }
+        let layout = Layout::array::<T>(len).expect("buffer size should not overflow");
+        self.alloc_layout(layout).cast()
     }
 
-    pub fn alloc_slice_from_iter<T>(&mut self, items: impl IntoIterator<Item = T>) -> Buffer<T> {
+    /// # Safety
+    /// Assumes that the first `len` values behind `ptr` are initialized.
+    unsafe fn buffer<T>(&self, ptr: NonNull<T>, len: usize) -> Buffer<T> {
+        Buffer {
+            ptr,
+            len,
+            #[cfg(debug_assertions)]
+            guard: self.guard(),
+            marker: PhantomData,
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn guard(&self) -> Guard {
+        Guard { epoch: self.epoch.clone(), created: self.epoch.load(Ordering::Relaxed) }
+    }
+
+    /// Allocates `size` values created by `default`.
+    /// # Safety
+    /// The returned [`Buffer`] has to be dropped, freed or moved into another arena
+    /// before this arena is dropped or [`Arena::free_all`] is called.
+    pub unsafe fn alloc_slice_with<T>(
+        &mut self,
+        size: usize,
+        default: impl Fn() -> T,
+    ) -> Buffer<T> {
+        let ptr = self.alloc_uninit::<T>(size);
+        for i in 0..size {
+            // SAFETY: `ptr` was allocated for `size` values (writing zero sized types is always valid)
+            unsafe { ptr.add(i).write(default()) };
+        }
+        // SAFETY: all values were initialized above
+        unsafe { self.buffer(ptr, size) }
+    }
+
+    /// Allocates all values of `items`.
+    /// # Safety
+    /// Same as [`Arena::alloc_slice_with`].
+    pub unsafe fn alloc_slice_from_iter<T>(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+    ) -> Buffer<T> {
         let iter = items.into_iter();
-        let stride = size_of::<T>();
-        // TODO handle zero sized types
-        let start = self.next_aligned::<T>();
-        self.0.reserve(start + iter.size_hint().0 - self.0.len());
-        self.0.resize(start, 0);
-        for mut item in iter {
-            // SAFETY: casting to bytes is always valid
-            let item = unsafe { cast_slice(slice::from_mut(&mut item)) };
-            let start = self.0.len();
-            self.0.resize(start + stride, 0);
-            self.0[start..].copy_from_slice(item);
+        let mut capacity = iter.size_hint().0;
+        let mut ptr = self.alloc_uninit::<T>(capacity);
+        let mut len = 0;
+        for item in iter {
+            if len == capacity && size_of::<T>() != 0 {
+                let grown = self.alloc_uninit::<T>((capacity * 2).max(4));
+                // SAFETY: both allocations are valid for at least `len` values and never overlap
+                unsafe { ptr::copy_nonoverlapping(ptr.as_ptr(), grown.as_ptr(), len) };
+                if capacity > 0 {
+                    self.release(ptr.cast(), capacity * size_of::<T>());
+                }
+                ptr = grown;
+                capacity = (capacity * 2).max(4);
+            }
+            // SAFETY: `len` is always smaller than `capacity` here (or `T` is zero sized)
+            unsafe { ptr.add(len).write(item) };
+            len += 1;
         }
-        // SAFETY: results from `next_aligned` will always return aligned values
-        unsafe {
-            ManuallyDrop::new(ThinBoxedSlice::from_raw(cast_slice(&mut self.0[start..]) as *mut _))
+        if capacity > len && size_of::<T>() != 0 {
+            // NOTE: buffers never keep spare capacity, so `Arena::free` can return all of it
+            // SAFETY: the spare capacity is still inside the allocation
+            self.release(unsafe { ptr.add(len) }.cast(), (capacity - len) * size_of::<T>());
         }
+        // SAFETY: all values up to `len` were initialized above
+        unsafe { self.buffer(ptr, len) }
     }
 
+    /// Moves the values of `items` into this arena, `items` points to the new location afterwards.
+    /// The old location is not dropped and will be reclaimed by [`Arena::free_all`] of its arena.
     /// # Safety
-    /// Assumes that original buffer is not used anymore after moving.
-    pub unsafe fn move_into<T>(&mut self, items: &Buffer<T>) -> Buffer<T> {
-        let size = items.len() * size_of::<T>();
-        let start = self.next_aligned::<T>();
-        self.0.resize(start + size, 0);
+    /// Same as [`Arena::alloc_slice_with`], `items` now belongs to this arena.
+    pub unsafe fn move_into<T>(&mut self, items: &mut Buffer<T>) {
+        items.check();
+        let ptr = self.alloc_uninit::<T>(items.len);
         // SAFETY: the nessesary amount of data was allocated beforehand
         // also input and output types are the same, so copying their bytes is always valid
-        unsafe {
-            ptr::copy_nonoverlapping(
-                items.as_ptr() as *const u8,
-                self.0[start..].as_mut_ptr(),
-                size,
-            )
-        };
-        // SAFETY: results from `next_aligned` will always return aligned values
-        unsafe {
-            ManuallyDrop::new(ThinBoxedSlice::from_raw(cast_slice(&mut self.0[start..]) as *mut _))
+        unsafe { ptr::copy_nonoverlapping(items.ptr.as_ptr(), ptr.as_ptr(), items.len) };
+        // NOTE: the old values were moved, so they are overwritten without dropping them
+        items.ptr = ptr;
+        #[cfg(debug_assertions)]
+        {
+            items.guard = self.guard();
         }
     }
 
+    /// Drops all values of `buffer` and makes its memory available for later allocations.
     /// # Safety
-    /// This does not check for use after free cases.
+    /// Assumes that `buffer` was allocated by this arena (or moved into it).
+    pub unsafe fn free<T>(&mut self, buffer: Buffer<T>) {
+        #[cfg(debug_assertions)]
+        assert!(
+            Arc::ptr_eq(&buffer.guard.epoch, &self.epoch),
+            "buffer has to be freed by the arena it was allocated in"
+        );
+        // NOTE: this is the whole allocation, see `Arena::alloc_slice_from_iter`
+        let size = buffer.len * size_of::<T>();
+        let ptr = buffer.ptr.cast::<u8>();
+        drop(buffer);
+        if size > 0 {
+            self.release(ptr, size);
+        }
+    }
+
+    /// Makes all memory available again while keeping the chunks allocated.
+    /// In debug builds this panics when a [`Buffer`] of this arena is still alive.
+    /// # Safety
+    /// Assumes that all [`Buffer`]s allocated before were dropped or moved to another arena.
     pub unsafe fn free_all(&mut self) {
-        self.0.clear();
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            1,
+            Arc::strong_count(&self.epoch),
+            "all buffers have to be dropped or moved before freeing the arena"
+        );
+        self.chunks.iter_mut().for_each(|chunk| chunk.used = 0);
+        self.current = 0;
+        self.free.clear();
+        self.by_size.clear();
+        #[cfg(debug_assertions)]
+        self.epoch.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl Drop for Arena {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        self.epoch.fetch_add(1, Ordering::Relaxed);
+        for chunk in &self.chunks {
+            // SAFETY: chunks are always allocated using their own layout
+            unsafe { alloc::dealloc(chunk.ptr.as_ptr(), chunk.layout) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    #[test]
+    fn buffers_are_aligned() {
+        let mut arena = Arena::new();
+        let bytes = unsafe { arena.alloc_slice_with(3, || 1u8) };
+        let words = unsafe { arena.alloc_slice_with(5, || 2u64) };
+        let pairs = unsafe { arena.alloc_slice_from_iter([(1u16, 2u128), (3, 4)]) };
+        assert_eq!(0, words.as_ptr().align_offset(align_of::<u64>()));
+        assert_eq!(0, pairs.as_ptr().align_offset(align_of::<(u16, u128)>()));
+        assert_eq!([1, 1, 1], *bytes);
+        assert_eq!([2; 5], *words);
+        assert_eq!([(1, 2), (3, 4)], *pairs);
+    }
+
+    #[test]
+    fn buffers_keep_their_address() {
+        let mut arena = Arena::new();
+        let first = unsafe { arena.alloc_slice_with(16, || 7u32) };
+        let address = first.as_ptr();
+        let others =
+            (0..1000).map(|i| unsafe { arena.alloc_slice_with(i, || i) }).collect::<Vec<_>>();
+        assert_eq!(address, first.as_ptr());
+        assert_eq!([7; 16], *first);
+        assert!(others.iter().enumerate().all(|(i, buffer)| buffer.iter().all(|x| *x == i)));
+    }
+
+    #[test]
+    fn zero_sized_types() {
+        let mut arena = Arena::new();
+        let units = unsafe { arena.alloc_slice_with(1000, || ()) };
+        let empty = unsafe { arena.alloc_slice_from_iter(std::iter::repeat_n((), 10)) };
+        assert_eq!(1000, units.len());
+        assert_eq!(10, empty.len());
+        assert!(arena.chunks.is_empty());
+    }
+
+    #[test]
+    fn iterator_with_wrong_size_hint() {
+        let mut other = Arena::new();
+        let mut arena = Arena::new();
+        let items = unsafe { arena.alloc_slice_from_iter((0..100u64).filter(|x| x % 3 == 0)) };
+        assert_eq!((0..100).step_by(3).collect::<Vec<_>>(), *items);
+        let expected = items.to_vec();
+        let mut moved = items;
+        unsafe { other.move_into(&mut moved) };
+        assert_eq!(expected, *moved);
+    }
+
+    #[test]
+    fn free_all_reuses_chunks() {
+        let mut arena = Arena::new();
+        drop(unsafe { arena.alloc_slice_with(CHUNK_SIZE, || 0u8) });
+        let chunks = arena.chunks.len();
+        unsafe { arena.free_all() };
+        drop(unsafe { arena.alloc_slice_with(CHUNK_SIZE, || 0u8) });
+        assert_eq!(chunks, arena.chunks.len());
+    }
+
+    /// Counts how often it was dropped.
+    #[derive(Debug)]
+    struct Tracked(Rc<Cell<usize>>, #[expect(dead_code)] Vec<u8>);
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn values_are_dropped_once() {
+        let drops = Rc::new(Cell::new(0));
+        let mut arena = [Arena::new(), Arena::new()];
+        let mut buffers = (0..4)
+            .map(|i| {
+                let item = || Tracked(drops.clone(), vec![0; 16]);
+                unsafe { arena[0].alloc_slice_with(i, item) }
+            })
+            .collect::<Vec<_>>();
+        let items = [(); 3].map(|_| Tracked(drops.clone(), Vec::new()));
+        buffers.push(unsafe { arena[0].alloc_slice_from_iter(items) });
+        drop(buffers.swap_remove(3));
+        assert_eq!(3, drops.get());
+        let [old, new] = &mut arena;
+        buffers.iter_mut().for_each(|buffer| unsafe { new.move_into(buffer) });
+        unsafe { old.free_all() };
+        assert_eq!(3, drops.get());
+        drop(buffers);
+        assert_eq!(9, drops.get());
+        unsafe { new.free_all() };
+    }
+
+    #[test]
+    fn freed_memory_is_reused() {
+        let mut arena = Arena::new();
+        let first = unsafe { arena.alloc_slice_with(8, || 1u64) };
+        let address = first.as_ptr() as usize;
+        let _other = unsafe { arena.alloc_slice_with(8, || 2u64) };
+        unsafe { arena.free(first) };
+        let smaller = unsafe { arena.alloc_slice_with(3, || 3u64) };
+        assert_eq!(address, smaller.as_ptr() as usize);
+        let rest = unsafe { arena.alloc_slice_with(5, || 4u32) };
+        assert_eq!(address + 3 * size_of::<u64>(), rest.as_ptr() as usize);
+        let bigger = unsafe { arena.alloc_slice_with(8, || 5u64) };
+        assert_ne!(address, bigger.as_ptr() as usize);
+        assert_eq!([3; 3], *smaller);
+        assert_eq!([4; 5], *rest);
+    }
+
+    #[test]
+    fn adjacent_free_blocks_are_merged() {
+        let mut arena = Arena::new();
+        let first = unsafe { arena.alloc_slice_with(8, || 1u64) };
+        let address = first.as_ptr() as usize;
+        let second = unsafe { arena.alloc_slice_with(8, || 2u64) };
+        let third = unsafe { arena.alloc_slice_with(8, || 3u64) };
+        let _last = unsafe { arena.alloc_slice_with(8, || 4u64) };
+        unsafe { arena.free(first) };
+        unsafe { arena.free(third) };
+        unsafe { arena.free(second) };
+        assert_eq!(1, arena.free.len());
+        let merged = unsafe { arena.alloc_slice_with(24, || 5u64) };
+        assert_eq!(address, merged.as_ptr() as usize);
+        assert!(arena.free.is_empty());
+    }
+
+    #[test]
+    fn free_blocks_at_the_end_return_to_their_chunk() {
+        let mut arena = Arena::new();
+        let first = unsafe { arena.alloc_slice_with(8, || 1u64) };
+        let second = unsafe { arena.alloc_slice_with(8, || 2u64) };
+        unsafe { arena.free(first) };
+        unsafe { arena.free(second) };
+        assert!(arena.free.is_empty());
+        assert_eq!(0, arena.chunks[0].used);
+    }
+
+    #[test]
+    fn growing_iterators_keep_no_spare_memory() {
+        let mut arena = Arena::new();
+        // NOTE: grows from 4 to 8 to 16 values, leaving 6 of them unused
+        let items = unsafe { arena.alloc_slice_from_iter((0..10u64).filter(|_| true)) };
+        let start = arena.chunks[0].ptr.as_ptr() as usize;
+        assert_eq!(start + 12 * size_of::<u64>(), items.as_ptr() as usize);
+        assert_eq!(22 * size_of::<u64>(), arena.chunks[0].used);
+        let reused = unsafe { arena.alloc_slice_with(12, || 0u64) };
+        assert_eq!(start, reused.as_ptr() as usize);
+        unsafe { arena.free(items) };
+        unsafe { arena.free(reused) };
+        assert!(arena.free.is_empty());
+        assert_eq!(0, arena.chunks[0].used);
+    }
+
+    #[test]
+    fn free_drops_values() {
+        let drops = Rc::new(Cell::new(0));
+        let mut arena = Arena::new();
+        let buffer = unsafe { arena.alloc_slice_with(5, || Tracked(drops.clone(), Vec::new())) };
+        unsafe { arena.free(buffer) };
+        assert_eq!(5, drops.get());
+        unsafe { arena.free_all() };
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic = "all buffers have to be dropped or moved before freeing the arena"]
+    fn free_all_with_alive_buffers_is_detected() {
+        let mut arena = Arena::new();
+        let _buffer = unsafe { arena.alloc_slice_with(4, || 0u32) };
+        unsafe { arena.free_all() };
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic = "buffer was used after its arena was freed"]
+    fn use_after_free_is_detected() {
+        // NOTE: this violates the safety contract on purpose
+        let buffer = unsafe { Arena::new().alloc_slice_with(4, || 0u32) };
+        let _ = buffer[0];
     }
 }
@@ -0,0 +1,420 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use rand::{Rng, seq::IndexedRandom};
+use thin_vec::ThinVec;
+
+use super::neat::{self, Neat};
+use crate::{
+    agent::{self, *},
+    arena::Arena,
+    numeric::{Activation, ActivationGene, ModulatedWeight, Sum, Weight, Weighted},
+    random::Random,
+};
+
+/// Genome of the network that generates the substrate.
+pub type Cppn = Neat<Activation, Weighted, Sum>;
+
+/// Inputs of the CPPN: position of the source, position of the target and a constant bias.
+const CPPN_INPUTS: usize = 5;
+/// Outputs of the CPPN: weight of the connection and bias of the target neuron.
+const CPPN_OUTPUTS: usize = 2;
+
+/// Gene of the generated [`Brain`] that is created from a single output of the CPPN.
+pub trait SubstrateGene {
+    fn from_cppn(value: f64) -> Self;
+}
+impl SubstrateGene for () {
+    fn from_cppn(_value: f64) -> Self {}
+}
+impl SubstrateGene for Weight {
+    fn from_cppn(value: f64) -> Self {
+        Self(value)
+    }
+}
+impl SubstrateGene for ModulatedWeight {
+    fn from_cppn(value: f64) -> Self {
+        Self { weight: value, modulator: None }
+    }
+}
+impl SubstrateGene for ActivationGene {
+    fn from_cppn(value: f64) -> Self {
+        Self { bias: value, ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CppnInterface;
+impl Phenotype for CppnInterface {
+    type ActionGene = ();
+    type SensorGene = ();
+}
+
+/// Value passed into the CPPN.
+struct Coordinate(f64);
+impl From<&Coordinate> for f64 {
+    fn from(value: &Coordinate) -> Self {
+        value.0
+    }
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub cppn:             neat::Config<Activation, Weighted>,
+    /// Positions of hidden neurons, sensors connect to all of them and they connect to all actions.
+    /// Sensors are connected to actions directly when this is empty.
+    pub hidden:           Vec<Position>,
+    /// Connections are only created when the magnitude of the CPPN output is above this value.
+    pub weight_threshold: f64,
+    /// Magnitude of the weight for a CPPN output of `1`.
+    pub max_weight:       f64,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cppn:             Default::default(),
+            hidden:           Vec::new(),
+            weight_threshold: 0.2,
+            max_weight:       3.0,
+        }
+    }
+}
+
+/// Indirect encoding following Hypercube-based NEAT.
+///
+/// A small CPPN evolved by [`Neat`] is queried for every pair of neurons in a geometric substrate
+/// to generate the weights of the actual [`Brain`].
+/// Sensors and actions are placed at [`Phenotype::sensor_position`] and
+/// [`Phenotype::action_position`], or spread evenly along `y = -1` and `y = 1` respectively.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct HyperNeat<A, P, C> {
+    cppn:       Cppn,
+    cppn_brain: Brain<Activation, Weighted>,
+    cppn_body:  Body<CppnInterface>,
+    marker:     PhantomData<fn(A, P, C)>,
+}
+impl<A, P, C> Debug for HyperNeat<A, P, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyperNeat")
+            .field("cppn", &self.cppn)
+            .field("cppn_brain", &self.cppn_brain)
+            .finish_non_exhaustive()
+    }
+}
+impl<A, P, C> Clone for HyperNeat<A, P, C> {
+    fn clone(&self) -> Self {
+        Self {
+            cppn:       self.cppn.clone(),
+            cppn_brain: self.cppn_brain.clone(),
+            cppn_body:  self.cppn_body.clone(),
+            marker:     PhantomData,
+        }
+    }
+}
+
+impl<A, P, C> HyperNeat<A, P, C>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    A: 'static
+        + for<'a> Activator<
+            Input<'a> = C::Output<'a>,
+            Output<'a> = P::Input<'a>,
+            Gene: SubstrateGene,
+        >,
+    P: 'static + for<'p> Propagator<Output<'p> = C::Input<'p>, Gene: SubstrateGene>,
+    C: 'static + Collector,
+{
+    /// Creates a minimal CPPN and the substrate it generates for the given interface.
+    pub fn minimal<X: Phenotype>(
+        phenotype: X,
+        sensors: impl IntoIterator<Item = X::SensorGene>,
+        actions: impl IntoIterator<Item = X::ActionGene>,
+        rng: &mut impl Rng,
+        config: &Config,
+    ) -> (Self, Brain<A, P>, Body<X>) {
+        let (cppn, cppn_brain, cppn_body) = Cppn::minimal(
+            CppnInterface,
+            [(); CPPN_INPUTS],
+            [(); CPPN_OUTPUTS],
+            rng,
+            &config.cppn,
+        );
+        let genome = Self { cppn, cppn_brain, cppn_body, marker: PhantomData };
+        let (brain, body) = genome.develop(
+            phenotype,
+            sensors.into_iter().collect(),
+            actions.into_iter().collect(),
+            config,
+        );
+        (genome, brain, body)
+    }
+
+    pub fn cppn(&self) -> (&Cppn, &Brain<Activation, Weighted>) {
+        (&self.cppn, &self.cppn_brain)
+    }
+
+    /// Scales a CPPN output into a weight, returns `None` when it is below the threshold.
+    fn weight(value: f64, config: &Config) -> Option<f64> {
+        let magnitude = value.abs().min(1.0);
+        (magnitude > config.weight_threshold).then(|| {
+            value.signum() * (magnitude - config.weight_threshold)
+                / (1.0 - config.weight_threshold)
+                * config.max_weight
+        })
+    }
+
+    /// Places sensors and actions without a [`Position`] evenly along a line.
+    fn layout(index: usize, count: usize, y: f64) -> Position {
+        let x = if count <= 1 { 0.0 } else { 2.0 * index as f64 / (count - 1) as f64 - 1.0 };
+        [x, y]
+    }
+
+    /// Generates the [`Brain`] by querying the CPPN for every part of the substrate.
+    fn develop<X: Phenotype>(
+        &self,
+        phenotype: X,
+        sensors: Vec<X::SensorGene>,
+        actions: Vec<X::ActionGene>,
+        config: &Config,
+    ) -> (Brain<A, P>, Body<X>) {
+        let sensor_positions = sensors.iter().enumerate().map(|(i, gene)| {
+            phenotype.sensor_position(gene).unwrap_or_else(|| Self::layout(i, sensors.len(), -1.0))
+        });
+        let action_positions = actions.iter().enumerate().map(|(i, gene)| {
+            phenotype.action_position(gene).unwrap_or_else(|| Self::layout(i, actions.len(), 1.0))
+        });
+        let layers =
+            [sensor_positions.collect(), config.hidden.clone(), action_positions.collect()];
+
+        let mut arena = Arena::new();
+        // SAFETY: `state` is declared after `arena`, so it is dropped first
+        let mut state = unsafe {
+            agent::State::<_, _, Sum>::create_for(&self.cppn_brain, &self.cppn_body, &mut arena)
+        };
+        let cppn_config = agent::Config::default();
+        let mut query = |from: Position, to: Position| {
+            let inputs = [from[0], from[1], to[0], to[1], 1.0].map(Coordinate);
+            let mut outputs = [0.0; CPPN_OUTPUTS];
+            state.reset();
+            state.step(&self.cppn_brain, &inputs, &mut outputs, &cppn_config);
+            outputs
+        };
+
+        let mut brain = Brain::new();
+        // NOTE: neurons are added layer by layer, so connections between layers are feed forward
+        let ids = layers.each_ref().map(|layer: &Vec<Position>| {
+            layer
+                .iter()
+                .map(|position| brain.add_neuron(A::Gene::from_cppn(query([0.0; 2], *position)[1])))
+                .collect::<Vec<_>>()
+        });
+        let pairs: &[(usize, usize)] =
+            if config.hidden.is_empty() { &[(0, 2)] } else { &[(0, 1), (1, 2)] };
+        for &(source, target) in pairs {
+            for (to, to_position) in ids[target].iter().zip(&layers[target]) {
+                for (from, from_position) in ids[source].iter().zip(&layers[source]) {
+                    let Some(weight) = Self::weight(query(*from_position, *to_position)[0], config)
+                    else {
+                        continue;
+                    };
+                    brain
+                        .add_connection(*from, *to, P::Gene::from_cppn(weight))
+                        .expect("connections between layers are always feed forward");
+                }
+            }
+        }
+
+        let [sensor_ids, _, action_ids] = ids;
+        let sensors = sensor_ids
+            .into_iter()
+            .zip(sensors)
+            .map(|(neuron, gene)| Sensor { neuron, gene })
+            .collect::<ThinVec<_>>();
+        let actions = action_ids
+            .into_iter()
+            .zip(actions)
+            .map(|(neuron, gene)| Action { neuron, gene })
+            .collect::<ThinVec<_>>();
+        // SAFETY: all sensors are added before all actions and connections never reorder them
+        let body = unsafe { Body::new_unchecked(sensors, actions, phenotype) };
+        (brain, body)
+    }
+
+    fn offspring<X: Phenotype>(
+        first: (&Self, &Body<X>),
+        second: Option<&Self>,
+        rng: &mut Random,
+        config: &Config,
+    ) -> (Self, Brain<A, P>, Body<X>) {
+        let parents = [Some(first.0), second]
+            .into_iter()
+            .flatten()
+            .map(|genome| (&genome.cppn, &genome.cppn_brain, &genome.cppn_body));
+        let (cppn, cppn_brain, cppn_body) = Cppn::spawn(parents, 2, rng, &config.cppn);
+        let genome = Self { cppn, cppn_brain, cppn_body, marker: PhantomData };
+        let body = first.1;
+        let (brain, body) = genome.develop(
+            body.phenotype().clone(),
+            body.iter_sensors().cloned().collect(),
+            body.iter_actions().cloned().collect(),
+            config,
+        );
+        (genome, brain, body)
+    }
+}
+
+impl<A, P, C> Genome for HyperNeat<A, P, C>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    A: 'static
+        + for<'a> Activator<
+            Input<'a> = C::Output<'a>,
+            Output<'a> = P::Input<'a>,
+            Gene: SubstrateGene,
+        >,
+    P: 'static + for<'p> Propagator<Output<'p> = C::Input<'p>, Gene: SubstrateGene>,
+    C: 'static + Collector,
+{
+    type Activator = A;
+    type Collector = C;
+    type Config = Config;
+    type Propagator = P;
+
+    /// Evolves the CPPNs of the parents using [`Neat`] and generates a new substrate
+    /// for the interface of the first parent.
+    fn populate<X: Phenotype>(
+        parents: impl IntoIterator<Item = (Self, Brain<A, P>, Body<X>)>,
+        parent_count: usize,
+        children_count: usize,
+        rng: &mut Random,
+        config: &Self::Config,
+    ) -> impl Iterator<Item = (Self, Brain<A, P>, Body<X>)> {
+        let parents = parents.into_iter().take(parent_count).collect::<Vec<_>>();
+        let children = if parents.is_empty() {
+            Vec::new()
+        } else {
+            (0..children_count)
+                .map(|i| {
+                    let (genome, _, body) = &parents[i % parents.len()];
+                    let second = parents.choose(rng).map(|(genome, ..)| genome);
+                    Self::offspring((genome, body), second, rng, config)
+                })
+                .collect()
+        };
+        children.into_iter()
+    }
+
+    /// Compares the CPPNs (see [`Neat`]) and the phenotypes of both agents.
+    fn distance<X: Phenotype>(
+        a: (&Self, &Brain<A, P>, &Body<X>),
+        b: (&Self, &Brain<A, P>, &Body<X>),
+        config: &Self::Config,
+    ) -> f64 {
+        Cppn::distance(
+            (&a.0.cppn, &a.0.cppn_brain, &a.0.cppn_body),
+            (&b.0.cppn, &b.0.cppn_brain, &b.0.cppn_body),
+            &config.cppn,
+        ) + config.cppn.phenotype_coefficient * a.2.phenotype().distance(b.2.phenotype())
+    }
+
+    /// # Panics
+    /// Panics when `parents` is empty, use [`HyperNeat::minimal`] to create an initial genome.
+    fn spawn<'a, X, I>(
+        parents: I,
+        count: usize,
+        rng: &mut Random,
+        config: &Self::Config,
+    ) -> (Self, Brain<A, P>, Body<X>)
+    where
+        X: 'a + Phenotype,
+        Self: 'a,
+        A: 'a,
+        P: 'a,
+        I: IntoIterator<Item = (&'a Self, &'a Brain<A, P>, &'a Body<X>)>,
+    {
+        let mut parents = parents.into_iter().take(count);
+        let (genome, _, body) = parents.next().expect("at least one parent is required");
+        let second = parents.next().map(|(genome, ..)| genome);
+        Self::offspring((genome, body), second, rng, config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::numeric::{ActivationMutation, Function};
+
+    /// Sensors are placed at the position stored in their gene.
+    #[derive(Debug, Clone)]
+    struct Grid;
+    impl Phenotype for Grid {
+        type ActionGene = ();
+        type SensorGene = Position;
+
+        fn sensor_position(&self, gene: &Self::SensorGene) -> Option<Position> {
+            Some(*gene)
+        }
+    }
+    type TestGenome = HyperNeat<Activation, Weighted, Sum>;
+
+    fn config(hidden: Vec<Position>) -> Config {
+        let activator =
+            ActivationMutation { functions: vec![Function::Tanh], ..Default::default() };
+        Config {
+            cppn: neat::Config { activator, ..Default::default() },
+            hidden,
+            weight_threshold: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn minimal(config: &Config) -> (TestGenome, Brain<Activation, Weighted>, Body<Grid>) {
+        let mut rng = Random::seed_from_u64(0);
+        let sensors = [[-1.0, -1.0], [1.0, -1.0], [0.0, -0.5]];
+        TestGenome::minimal(Grid, sensors, [(), ()], &mut rng, config)
+    }
+
+    #[test]
+    fn substrate_connects_layers() {
+        let config = config(Vec::new());
+        let (_, brain, body) = minimal(&config);
+        assert_eq!(5, brain.neurons().len());
+        assert_eq!(3 * 2, brain.connections().len());
+        assert_eq!(Ok(()), body.validate(brain.order()));
+
+        let config = self::config(vec![[0.0, 0.0]]);
+        let (_, brain, body) = minimal(&config);
+        assert_eq!(6, brain.neurons().len());
+        assert_eq!(3 + 2, brain.connections().len());
+        assert_eq!(Ok(()), body.validate(brain.order()));
+    }
+
+    #[test]
+    fn offspring_keep_interface() {
+        let config = config(Vec::new());
+        let parent = minimal(&config);
+        let parents = [parent.clone(), minimal(&config)];
+        let mut rng = Random::seed_from_u64(1);
+        for (genome, brain, body) in TestGenome::populate(parents, 2, 8, &mut rng, &config) {
+            assert_eq!(Ok(()), body.validate(brain.order()));
+            assert!(body.iter_sensors().eq(parent.2.iter_sensors()));
+            assert_eq!(2, body.action_count());
+            let agent = (&genome, &brain, &body);
+            assert_eq!(0.0, TestGenome::distance(agent, agent, &config));
+        }
+        let (_, brain, body) =
+            TestGenome::spawn([(&parent.0, &parent.1, &parent.2)], 1, &mut rng, &config);
+        assert_eq!(Ok(()), body.validate(brain.order()));
+    }
+
+    #[test]
+    fn interface_is_spread_evenly() {
+        assert_eq!([-1.0, 1.0], TestGenome::layout(0, 3, 1.0));
+        assert_eq!([0.0, 1.0], TestGenome::layout(1, 3, 1.0));
+        assert_eq!([1.0, 1.0], TestGenome::layout(2, 3, 1.0));
+        assert_eq!([0.0, -1.0], TestGenome::layout(0, 1, -1.0));
+    }
+}
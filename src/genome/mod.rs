@@ -0,0 +1,2 @@
+pub mod hyperneat;
+pub mod neat;
@@ -0,0 +1,900 @@
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    marker::PhantomData,
+    sync::{Mutex, PoisonError},
+};
+
+use rand::{Rng, seq::IndexedRandom};
+use thin_vec::ThinVec;
+
+use crate::{agent::*, random::Random};
+
+/// Historical marking used to align genes of different genomes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Innovation(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum NodeKey {
+    Sensor(usize),
+    Action(usize),
+    Split(Innovation),
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Registry {
+    next:        u64,
+    #[cfg_attr(feature = "serde", serde(with = "entries"))]
+    nodes:       HashMap<NodeKey, Innovation>,
+    #[cfg_attr(feature = "serde", serde(with = "entries"))]
+    connections: HashMap<(Innovation, Innovation, bool), Innovation>,
+}
+
+/// Stores maps as a list of entries, since most formats only support string keys.
+#[cfg(feature = "serde")]
+mod entries {
+    use std::{collections::HashMap, hash::Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(HashMap::from_iter)
+    }
+}
+impl Registry {
+    fn fresh(&mut self) -> Innovation {
+        self.next += 1;
+        Innovation(self.next - 1)
+    }
+}
+
+/// Global record of all [`Innovation`]s.
+/// The same structural mutation will always receive the same [`Innovation`].
+#[derive(Debug, Default)]
+pub struct Innovations(Mutex<Registry>);
+#[cfg(feature = "serde")]
+impl serde::Serialize for Innovations {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.registry().serialize(serializer)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Innovations {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Registry::deserialize(deserializer).map(|registry| Self(Mutex::new(registry)))
+    }
+}
+impl Innovations {
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn node(&self, key: NodeKey) -> Innovation {
+        let mut registry = self.registry();
+        match registry.nodes.get(&key) {
+            Some(innovation) => *innovation,
+            None => {
+                let innovation = registry.fresh();
+                registry.nodes.insert(key, innovation);
+                innovation
+            },
+        }
+    }
+
+    fn connection(&self, from: Innovation, to: Innovation, recurrent: bool) -> Innovation {
+        let mut registry = self.registry();
+        let key = (from, to, recurrent);
+        match registry.connections.get(&key) {
+            Some(innovation) => *innovation,
+            None => {
+                let innovation = registry.fresh();
+                registry.connections.insert(key, innovation);
+                innovation
+            },
+        }
+    }
+
+    /// Returns the [`Innovation`] of a connection without registering it.
+    fn find_connection(
+        &self,
+        from: Innovation,
+        to: Innovation,
+        recurrent: bool,
+    ) -> Option<Innovation> {
+        self.registry().connections.get(&(from, to, recurrent)).copied()
+    }
+
+    fn fresh(&self) -> Innovation {
+        self.registry().fresh()
+    }
+}
+
+/// Gene data that can be created and changed by [`Neat`].
+pub trait NeatGene: Debug + Clone {
+    type Config: Debug + Default;
+    /// Creates the gene of a newly added [`Neuron`] or [`Connection`].
+    fn create(rng: &mut impl Rng, config: &Self::Config) -> Self;
+    /// Randomly changes the gene.
+    fn mutate(&mut self, rng: &mut impl Rng, config: &Self::Config);
+    /// Returns the genes of the incoming and outgoing connection when a neuron is inserted into a connection.
+    #[expect(unused_variables)]
+    fn split(self, rng: &mut impl Rng, config: &Self::Config) -> (Self, Self) {
+        (self.clone(), self)
+    }
+    /// Returns how different two genes are, used for the average gene difference in [`Genome::distance`].
+    #[expect(unused_variables)]
+    fn distance(&self, other: &Self, config: &Self::Config) -> f64 {
+        0.0
+    }
+}
+impl NeatGene for () {
+    type Config = ();
+
+    fn create(_rng: &mut impl Rng, _config: &Self::Config) -> Self {}
+
+    fn mutate(&mut self, _rng: &mut impl Rng, _config: &Self::Config) {}
+}
+
+#[derive(Debug)]
+pub struct Config<A, P>
+where
+    A: Activator<Gene: NeatGene>,
+    P: Propagator<Gene: NeatGene>,
+{
+    pub activator:                <A::Gene as NeatGene>::Config,
+    pub propagator:               <P::Gene as NeatGene>::Config,
+    /// Chance that a child is created from two parents instead of a single one.
+    pub crossover_chance:         f64,
+    /// Chance for each neuron gene to be mutated.
+    pub mutate_neuron_chance:     f64,
+    /// Chance for each connection gene to be mutated.
+    pub mutate_connection_chance: f64,
+    /// Chance that a connection is split by a new neuron.
+    pub add_neuron_chance:        f64,
+    /// Chance that a new connection is added.
+    pub add_connection_chance:    f64,
+    /// Chance that a new connection that would close a cycle is added as a recurrent connection.
+    pub recurrent_chance:         f64,
+    /// Chance for each sensor and action gene to be mutated by the [`Phenotype`].
+    pub mutate_interface_chance:  f64,
+    /// Chance that a sensor and an action are added when the [`Phenotype`] supports it.
+    pub add_interface_chance:     f64,
+    /// Chance that a sensor and an action are removed when the [`Phenotype`] allows it.
+    pub remove_interface_chance:  f64,
+    /// Weight of connections that are newer than all connections of the other genome.
+    pub excess_coefficient:       f64,
+    /// Weight of connections that are missing in the other genome.
+    pub disjoint_coefficient:     f64,
+    /// Weight of the average gene difference of matching connections.
+    pub gene_coefficient:         f64,
+    /// Weight of [`Phenotype::distance`].
+    pub phenotype_coefficient:    f64,
+    pub innovations:              Innovations,
+}
+impl<A, P> Default for Config<A, P>
+where
+    A: Activator<Gene: NeatGene>,
+    P: Propagator<Gene: NeatGene>,
+{
+    fn default() -> Self {
+        Self {
+            activator:                Default::default(),
+            propagator:               Default::default(),
+            crossover_chance:         0.75,
+            mutate_neuron_chance:     0.1,
+            mutate_connection_chance: 0.8,
+            add_neuron_chance:        0.03,
+            add_connection_chance:    0.05,
+            recurrent_chance:         0.0,
+            mutate_interface_chance:  0.1,
+            add_interface_chance:     0.01,
+            remove_interface_chance:  0.01,
+            excess_coefficient:       1.0,
+            disjoint_coefficient:     1.0,
+            gene_coefficient:         0.4,
+            phenotype_coefficient:    1.0,
+            innovations:              Innovations::default(),
+        }
+    }
+}
+
+/// Direct encoding following NeuroEvolution of Augmenting Topologies.
+///
+/// Parents are expected to be sorted by fitness, best first.
+/// Children inherit the topology of their first parent, genes of matching neurons and connections
+/// are picked randomly from both parents.
+///
+/// [`Innovation`]s are only meaningful together with the [`Innovations`] they were created by.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct Neat<A, P, C> {
+    nodes:  HashMap<NeuronID, Innovation>,
+    marker: PhantomData<fn(A, P, C)>,
+}
+impl<A, P, C> Debug for Neat<A, P, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Neat").field("nodes", &self.nodes).finish()
+    }
+}
+impl<A, P, C> Clone for Neat<A, P, C> {
+    fn clone(&self) -> Self {
+        Self { nodes: self.nodes.clone(), marker: PhantomData }
+    }
+}
+
+impl<A, P, C> Neat<A, P, C>
+where
+    A: Activator<Gene: NeatGene>,
+    P: Propagator<Gene: NeatGene>,
+    C: Collector,
+{
+    /// Creates a network where every sensor is connected to every action.
+    pub fn minimal<X: Phenotype>(
+        phenotype: X,
+        sensors: impl IntoIterator<Item = X::SensorGene>,
+        actions: impl IntoIterator<Item = X::ActionGene>,
+        rng: &mut impl Rng,
+        config: &Config<A, P>,
+    ) -> (Self, Brain<A, P>, Body<X>) {
+        let mut genome = Self { nodes: HashMap::new(), marker: PhantomData };
+        let mut brain = Brain::new();
+        let sensors = sensors
+            .into_iter()
+            .enumerate()
+            .map(|(i, gene)| {
+                let neuron = brain.add_neuron(A::Gene::create(rng, &config.activator));
+                genome.nodes.insert(neuron, config.innovations.node(NodeKey::Sensor(i)));
+                Sensor { neuron, gene }
+            })
+            .collect::<ThinVec<_>>();
+        let actions = actions
+            .into_iter()
+            .enumerate()
+            .map(|(i, gene)| {
+                let neuron = brain.add_neuron(A::Gene::create(rng, &config.activator));
+                genome.nodes.insert(neuron, config.innovations.node(NodeKey::Action(i)));
+                Action { neuron, gene }
+            })
+            .collect::<ThinVec<_>>();
+        for action in &actions {
+            for sensor in &sensors {
+                let gene = P::Gene::create(rng, &config.propagator);
+                brain
+                    .add_connection(sensor.neuron, action.neuron, gene)
+                    .expect("sensors are always ordered before actions");
+                genome.register_connection(sensor.neuron, action.neuron, false, config);
+            }
+        }
+        // SAFETY: all sensors are added before all actions and connections never reorder them
+        let body = unsafe { Body::new_unchecked(sensors, actions, phenotype) };
+        (genome, brain, body)
+    }
+
+    /// Returns the [`Innovation`] of a neuron.
+    pub fn innovation(&self, neuron: NeuronID) -> Option<Innovation> {
+        self.nodes.get(&neuron).copied()
+    }
+
+    /// Returns the [`Innovation`] of a connection.
+    /// # Panics
+    /// Panics when the connection is not part of a brain belonging to this genome.
+    pub fn connection_innovation(&self, conn: &Connection<P>, config: &Config<A, P>) -> Innovation {
+        let node = |id| self.innovation(id).expect("all neurons should have an innovation");
+        config
+            .innovations
+            .find_connection(node(conn.from), node(conn.to), conn.recurrent)
+            .expect("all connections should have been registered")
+    }
+
+    /// Registers the [`Innovation`] of a connection that was just added to the brain.
+    fn register_connection(
+        &self,
+        from: NeuronID,
+        to: NeuronID,
+        recurrent: bool,
+        config: &Config<A, P>,
+    ) -> Innovation {
+        let node = |id| self.innovation(id).expect("all neurons should have an innovation");
+        config.innovations.connection(node(from), node(to), recurrent)
+    }
+
+    fn offspring<X: Phenotype>(
+        first: (&Self, &Brain<A, P>, &Body<X>),
+        second: Option<(&Self, &Brain<A, P>, &Body<X>)>,
+        rng: &mut impl Rng,
+        config: &Config<A, P>,
+    ) -> (Self, Brain<A, P>, Body<X>) {
+        let (mut genome, mut brain, mut body) =
+            (first.0.clone(), first.1.clone(), first.2.clone());
+        if let Some(second) = second.filter(|_| rng.random_bool(config.crossover_chance)) {
+            genome.crossover(&mut brain, (second.0, second.1), rng);
+        }
+        genome.mutate(&mut brain, &body, rng, config);
+        genome.mutate_body(&mut brain, &mut body, rng, config);
+        // NOTE: new connections can move interface neurons in the order
+        body.sort(brain.order()).expect("all interface neurons should be part of the brain");
+        (genome, brain, body)
+    }
+
+    /// Takes genes of matching neurons and connections from `other` with a chance of 50%.
+    fn crossover(&self, brain: &mut Brain<A, P>, other: (&Self, &Brain<A, P>), rng: &mut impl Rng) {
+        let other_ids =
+            other.0.nodes.iter().map(|(id, node)| (*node, *id)).collect::<HashMap<_, _>>();
+        let into_other = self
+            .nodes
+            .iter()
+            .filter_map(|(id, node)| other_ids.get(node).map(|other| (*id, *other)))
+            .collect::<HashMap<_, _>>();
+        let from_other =
+            into_other.iter().map(|(id, other)| (*other, *id)).collect::<HashMap<_, _>>();
+        let neurons = brain.neurons().iter().map(|neuron| neuron.id).collect::<ThinVec<_>>();
+        for id in neurons {
+            let Some(neuron) = into_other.get(&id).and_then(|other_id| other.1.neuron(*other_id))
+            else {
+                continue;
+            };
+            if rng.random_bool(0.5) {
+                *brain.activator_gene_mut(id).expect("neuron is part of the brain") =
+                    neuron.activator_gene.clone();
+            }
+        }
+        let mut replacements = ThinVec::new();
+        for conn in brain.connections() {
+            let (Some(from), Some(to)) = (into_other.get(&conn.from), into_other.get(&conn.to))
+            else {
+                continue;
+            };
+            let Some(matching) = other.1.connections_to(*to).iter().find(|other| {
+                other.from == *from && other.recurrent == conn.recurrent
+            }) else {
+                continue;
+            };
+            let compatible = P::referenced_neurons(&matching.propagator_gene)
+                .all(|id| from_other.contains_key(id.borrow()));
+            if compatible && rng.random_bool(0.5) {
+                let mut gene = matching.propagator_gene.clone();
+                P::remap_gene(&mut gene, &from_other);
+                replacements.push((conn.from, conn.to, gene));
+            }
+        }
+        for (from, to, gene) in replacements {
+            if let Some(index) = brain.find_connection(from, to) {
+                // NOTE: the gene stays unchanged when it would lead to an invalid order
+                let _ = brain.update_connection(index, |old| *old = gene);
+            }
+        }
+    }
+
+    fn mutate<X: Phenotype>(
+        &mut self,
+        brain: &mut Brain<A, P>,
+        body: &Body<X>,
+        rng: &mut impl Rng,
+        config: &Config<A, P>,
+    ) {
+        for i in 0..brain.neurons().len() {
+            if rng.random_bool(config.mutate_neuron_chance) {
+                let id = brain.neurons()[i].id;
+                let gene = brain.activator_gene_mut(id).expect("neuron is part of the brain");
+                gene.mutate(rng, &config.activator);
+            }
+        }
+        // NOTE: updating a connection can move it, so they are looked up again by their neurons
+        let connections =
+            brain.connections().iter().map(|conn| (conn.from, conn.to)).collect::<ThinVec<_>>();
+        for (from, to) in connections {
+            if rng.random_bool(config.mutate_connection_chance) {
+                let index =
+                    brain.find_connection(from, to).expect("connection is part of the brain");
+                // NOTE: the gene stays unchanged when it would lead to an invalid order
+                let _ = brain.update_connection(index, |gene| gene.mutate(rng, &config.propagator));
+            }
+        }
+        if rng.random_bool(config.add_connection_chance) {
+            self.add_connection(brain, body, rng, config);
+        }
+        if rng.random_bool(config.add_neuron_chance) {
+            self.add_neuron(brain, rng, config);
+        }
+    }
+
+    fn add_connection<X: Phenotype>(
+        &mut self,
+        brain: &mut Brain<A, P>,
+        body: &Body<X>,
+        rng: &mut impl Rng,
+        config: &Config<A, P>,
+    ) {
+        let sensors = body.iter_sensor_neurons().collect::<HashSet<_>>();
+        let targets = brain
+            .neurons()
+            .iter()
+            .map(|neuron| neuron.id)
+            .filter(|id| !sensors.contains(id))
+            .collect::<ThinVec<_>>();
+        let (Some(from), Some(&to)) = (brain.neurons().choose(rng), targets.choose(rng)) else {
+            return;
+        };
+        let from = from.id;
+        if brain.find_connection(from, to).is_some() {
+            return;
+        }
+        let gene = P::Gene::create(rng, &config.propagator);
+        let recurrent = match brain.add_connection(from, to, gene.clone()) {
+            Ok(_) => false,
+            Err(BrainError::Cycle { .. }) if rng.random_bool(config.recurrent_chance) =>
+                brain.add_recurrent_connection(from, to, gene).is_ok(),
+            Err(_) => return,
+        };
+        self.register_connection(from, to, recurrent, config);
+    }
+
+    /// Adds, removes and changes sensors and actions using the hooks of the [`Phenotype`].
+    fn mutate_body<X: Phenotype>(
+        &mut self,
+        brain: &mut Brain<A, P>,
+        body: &mut Body<X>,
+        rng: &mut impl Rng,
+        config: &Config<A, P>,
+    ) {
+        body.mutate_genes(rng, config.mutate_interface_chance);
+        if rng.random_bool(config.remove_interface_chance) {
+            let removable = body
+                .iter_sensor_neurons()
+                .zip(body.iter_sensors())
+                .filter(|(_, gene)| body.phenotype().can_remove_sensor(gene))
+                .map(|(id, _)| id)
+                .collect::<ThinVec<_>>();
+            if let Some(&id) = removable.choose(rng) {
+                body.remove_sensor(brain, id);
+                self.nodes.remove(&id);
+            }
+        }
+        if rng.random_bool(config.remove_interface_chance) {
+            let removable = body
+                .iter_action_neurons()
+                .zip(body.iter_actions())
+                .filter(|(_, gene)| body.phenotype().can_remove_action(gene))
+                .map(|(id, _)| id)
+                .collect::<ThinVec<_>>();
+            if let Some(&id) = removable.choose(rng) {
+                body.remove_action(brain, id);
+                self.nodes.remove(&id);
+            }
+        }
+        // NOTE: new interface neurons are not aligned by position, so they never share innovations
+        if rng.random_bool(config.add_interface_chance) {
+            if let Some(gene) = body.phenotype().create_sensor(rng) {
+                let id = body.add_sensor(brain, gene, A::Gene::create(rng, &config.activator));
+                self.nodes.insert(id, config.innovations.fresh());
+                let actions = body.iter_action_neurons().collect::<ThinVec<_>>();
+                if let Some(&to) = actions.choose(rng) {
+                    self.connect_interface(brain, id, to, rng, config);
+                }
+            }
+        }
+        if rng.random_bool(config.add_interface_chance) {
+            if let Some(gene) = body.phenotype().create_action(rng) {
+                let id = body.add_action(brain, gene, A::Gene::create(rng, &config.activator));
+                self.nodes.insert(id, config.innovations.fresh());
+                let sensors = body.iter_sensor_neurons().collect::<ThinVec<_>>();
+                if let Some(&from) = sensors.choose(rng) {
+                    self.connect_interface(brain, from, id, rng, config);
+                }
+            }
+        }
+    }
+
+    /// Connects a new interface neuron, so it takes part in the simulation right away.
+    fn connect_interface(
+        &self,
+        brain: &mut Brain<A, P>,
+        from: NeuronID,
+        to: NeuronID,
+        rng: &mut impl Rng,
+        config: &Config<A, P>,
+    ) {
+        let gene = P::Gene::create(rng, &config.propagator);
+        brain
+            .add_connection(from, to, gene)
+            .expect("new neurons have no connections that could close a cycle");
+        self.register_connection(from, to, false, config);
+    }
+
+    fn add_neuron(&mut self, brain: &mut Brain<A, P>, rng: &mut impl Rng, config: &Config<A, P>) {
+        let Some(conn) = brain.connections().choose(rng) else { return };
+        let (from, to, recurrent) = (conn.from, conn.to, conn.recurrent);
+        let innovation = self.connection_innovation(conn, config);
+        let gene = A::Gene::create(rng, &config.activator);
+        let Ok(id) = brain.split_connection(from, to, gene, |gene| gene.split(rng, &config.propagator))
+        else {
+            return;
+        };
+        let mut node = config.innovations.node(NodeKey::Split(innovation));
+        // NOTE: the same connection can be split again after it was added back later
+        if self.nodes.values().any(|other| *other == node) {
+            node = config.innovations.fresh();
+        }
+        self.nodes.insert(id, node);
+        // NOTE: see `Brain::split_connection`, only the outgoing connection stays recurrent
+        self.register_connection(from, id, false, config);
+        self.register_connection(id, to, recurrent, config);
+    }
+}
+
+impl<A, P, C> Genome for Neat<A, P, C>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    A: 'static
+        + for<'a> Activator<Input<'a> = C::Output<'a>, Output<'a> = P::Input<'a>, Gene: NeatGene>,
+    P: 'static + for<'p> Propagator<Output<'p> = C::Input<'p>, Gene: NeatGene>,
+    C: 'static + Collector,
+{
+    type Activator = A;
+    type Collector = C;
+    type Config = Config<A, P>;
+    type Propagator = P;
+
+    fn populate<X: Phenotype>(
+        parents: impl IntoIterator<Item = (Self, Brain<A, P>, Body<X>)>,
+        parent_count: usize,
+        children_count: usize,
+        rng: &mut Random,
+        config: &Self::Config,
+    ) -> impl Iterator<Item = (Self, Brain<A, P>, Body<X>)> {
+        let parents = parents.into_iter().take(parent_count).collect::<Vec<_>>();
+        let children = if parents.is_empty() {
+            Vec::new()
+        } else {
+            (0..children_count)
+                .map(|i| {
+                    let (genome, brain, body) = &parents[i % parents.len()];
+                    let second = parents.choose(rng).map(|(g, b, x)| (g, b, x));
+                    Self::offspring((genome, brain, body), second, rng, config)
+                })
+                .collect()
+        };
+        children.into_iter()
+    }
+
+    /// Compatibility distance `c1 * E / N + c2 * D / N + c3 * W`
+    /// with `E` excess connections, `D` disjoint connections, `N` connections of the larger genome
+    /// and `W` average gene difference of matching connections.
+    fn distance<X: Phenotype>(
+        a: (&Self, &Brain<A, P>, &Body<X>),
+        b: (&Self, &Brain<A, P>, &Body<X>),
+        config: &Self::Config,
+    ) -> f64 {
+        let genes = |genome: &Self, brain: &Brain<A, P>| {
+            brain
+                .connections()
+                .iter()
+                .map(|conn| {
+                    (genome.connection_innovation(conn, config), conn.propagator_gene.clone())
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let (a_genes, b_genes) = (genes(a.0, a.1), genes(b.0, b.1));
+        let a_max = a_genes.keys().max().copied();
+        let b_max = b_genes.keys().max().copied();
+        let (mut excess, mut disjoint, mut matching, mut difference) = (0, 0, 0, 0.0);
+        for (innovation, gene) in &a_genes {
+            match b_genes.get(innovation) {
+                Some(other) => {
+                    matching += 1;
+                    difference += gene.distance(other, &config.propagator);
+                },
+                None if Some(*innovation) > b_max => excess += 1,
+                None => disjoint += 1,
+            }
+        }
+        for innovation in b_genes.keys().filter(|innovation| !a_genes.contains_key(innovation)) {
+            if Some(*innovation) > a_max {
+                excess += 1;
+            } else {
+                disjoint += 1;
+            }
+        }
+        let size = a_genes.len().max(b_genes.len()).max(1) as f64;
+        let difference = if matching == 0 { 0.0 } else { difference / matching as f64 };
+        config.excess_coefficient * excess as f64 / size
+            + config.disjoint_coefficient * disjoint as f64 / size
+            + config.gene_coefficient * difference
+            + config.phenotype_coefficient * a.2.phenotype().distance(b.2.phenotype())
+    }
+
+    /// # Panics
+    /// Panics when `parents` is empty, use [`Neat::minimal`] to create an initial genome.
+    fn spawn<'a, X, I>(
+        parents: I,
+        count: usize,
+        rng: &mut Random,
+        config: &Self::Config,
+    ) -> (Self, Brain<A, P>, Body<X>)
+    where
+        X: 'a + Phenotype,
+        Self: 'a,
+        A: 'a,
+        P: 'a,
+        I: IntoIterator<Item = (&'a Self, &'a Brain<A, P>, &'a Body<X>)>,
+    {
+        let mut parents = parents.into_iter().take(count);
+        let first = parents.next().expect("at least one parent is required");
+        Self::offspring(first, parents.next(), rng, config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::test_util::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Weight(f64);
+    impl NeatGene for Weight {
+        type Config = ();
+
+        fn create(rng: &mut impl Rng, _config: &Self::Config) -> Self {
+            Self(rng.random_range(-1.0..1.0))
+        }
+
+        fn mutate(&mut self, rng: &mut impl Rng, _config: &Self::Config) {
+            self.0 += rng.random_range(-0.1..0.1);
+        }
+
+        fn split(self, _rng: &mut impl Rng, _config: &Self::Config) -> (Self, Self) {
+            (Self(1.0), self)
+        }
+
+        fn distance(&self, other: &Self, _config: &Self::Config) -> f64 {
+            (self.0 - other.0).abs()
+        }
+    }
+    #[derive(Debug, Default)]
+    struct Weighted;
+    impl Propagator for Weighted {
+        type Config = ();
+        type Gene = Weight;
+        type Input<'i>
+            = f64
+        where
+            Self: 'i;
+        type Output<'o>
+            = f64
+        where
+            Self: 'o;
+
+        fn modulation(
+            &self,
+            _gene: &Self::Gene,
+            _config: &Self::Config,
+        ) -> impl Iterator<Item: Borrow<NeuronID>> {
+            std::iter::empty::<NeuronID>()
+        }
+
+        fn propagate(
+            &mut self,
+            input: Self::Input<'_>,
+            _modulation: &[Self::Input<'_>],
+            gene: &Self::Gene,
+            _config: &Self::Config,
+        ) -> Self::Output<'_> {
+            input * gene.0
+        }
+    }
+    #[derive(Debug, Clone)]
+    struct GrowingPhenotype;
+    impl Phenotype for GrowingPhenotype {
+        type ActionGene = u32;
+        type SensorGene = u32;
+
+        fn create_sensor(&self, rng: &mut impl Rng) -> Option<Self::SensorGene> {
+            Some(rng.random())
+        }
+
+        fn create_action(&self, rng: &mut impl Rng) -> Option<Self::ActionGene> {
+            Some(rng.random())
+        }
+
+        fn can_remove_sensor(&self, gene: &Self::SensorGene) -> bool {
+            gene % 2 == 0
+        }
+
+        fn can_remove_action(&self, gene: &Self::ActionGene) -> bool {
+            gene % 2 == 0
+        }
+    }
+    type TestNeat = Neat<Value, Weighted, Sum>;
+    type TestConfig = Config<Value, Weighted>;
+
+    #[test]
+    fn minimal_connects_all_sensors_to_all_actions() {
+        let mut rng = Random::seed_from_u64(0);
+        let config = TestConfig::default();
+        let (genome, brain, body) =
+            TestNeat::minimal(TestPhenotype, [(); 3], [(); 2], &mut rng, &config);
+        assert_eq!(5, brain.neurons().len());
+        assert_eq!(6, brain.connections().len());
+        assert_eq!(3, body.sensor_count());
+        assert_eq!(2, body.action_count());
+        let (other, ..) = TestNeat::minimal(TestPhenotype, [(); 3], [(); 2], &mut rng, &config);
+        for neuron in brain.neurons() {
+            assert_eq!(genome.innovation(neuron.id), other.innovation(neuron.id));
+        }
+    }
+
+    #[test]
+    fn structural_mutations_share_innovations() {
+        let mut rng = Random::seed_from_u64(1);
+        let config = TestConfig {
+            crossover_chance: 0.0,
+            add_neuron_chance: 1.0,
+            add_connection_chance: 1.0,
+            recurrent_chance: 1.0,
+            ..Default::default()
+        };
+        let parent = TestNeat::minimal(TestPhenotype, [(); 2], [(); 1], &mut rng, &config);
+        let mut children = Vec::new();
+        for _ in 0..20 {
+            let child = TestNeat::spawn([(&parent.0, &parent.1, &parent.2)], 1, &mut rng, &config);
+            assert_eq!(4, child.1.neurons().len());
+            for conn in child.1.connections() {
+                for (before, after) in conn.dependencies() {
+                    assert!(child.1.order().index(before) < child.1.order().index(after));
+                }
+            }
+            children.push(child);
+        }
+        let mut splits = HashMap::new();
+        for (genome, brain, _) in &children {
+            let added = brain
+                .neurons()
+                .iter()
+                .find(|neuron| parent.0.innovation(neuron.id).is_none())
+                .unwrap();
+            let incoming = &brain.connections_to(added.id)[0];
+            assert_eq!(Weight(1.0), incoming.propagator_gene);
+            let node = genome.innovation(added.id).unwrap();
+            let split = (incoming.from, brain.connections().iter().find(|c| c.from == added.id));
+            let split = (split.0, split.1.unwrap().to);
+            assert_eq!(node, *splits.entry(split).or_insert(node));
+        }
+    }
+
+    #[test]
+    fn interface_mutations_keep_body_sorted() {
+        let mut rng = Random::seed_from_u64(5);
+        let config = TestConfig {
+            crossover_chance: 0.0,
+            add_connection_chance: 1.0,
+            recurrent_chance: 1.0,
+            add_interface_chance: 1.0,
+            remove_interface_chance: 0.5,
+            ..Default::default()
+        };
+        let mut agent = TestNeat::minimal(GrowingPhenotype, [0, 2], [4], &mut rng, &config);
+        for _ in 0..50 {
+            agent = TestNeat::offspring((&agent.0, &agent.1, &agent.2), None, &mut rng, &config);
+            let (genome, brain, body) = &agent;
+            body.validate(brain.order()).unwrap();
+            assert_eq!(brain.neurons().len(), genome.nodes.len());
+            for neuron in brain.neurons() {
+                assert!(genome.innovation(neuron.id).is_some());
+            }
+        }
+        assert!(agent.2.sensor_count() > 2);
+        assert!(agent.2.action_count() > 1);
+    }
+
+    #[test]
+    fn crossover_keeps_topology_of_first_parent() {
+        let mut rng = Random::seed_from_u64(2);
+        let config = TestConfig {
+            crossover_chance: 1.0,
+            mutate_neuron_chance: 0.0,
+            mutate_connection_chance: 0.0,
+            add_neuron_chance: 0.0,
+            add_connection_chance: 0.0,
+            ..Default::default()
+        };
+        let first = TestNeat::minimal(TestPhenotype, [(); 2], [(); 1], &mut rng, &config);
+        let mut second = TestNeat::minimal(TestPhenotype, [(); 2], [(); 1], &mut rng, &config);
+        second.0.add_neuron(&mut second.1, &mut rng, &config);
+        let parents = [first.clone(), second.clone()];
+        let children = TestNeat::populate(parents, 2, 8, &mut rng, &config).collect::<Vec<_>>();
+        assert_eq!(8, children.len());
+        for (i, (_, brain, _)) in children.iter().enumerate() {
+            let parent = if i % 2 == 0 { &first.1 } else { &second.1 };
+            assert_eq!(parent.neurons().len(), brain.neurons().len());
+            assert_eq!(parent.connections().len(), brain.connections().len());
+            for conn in brain.connections() {
+                let weight = &conn.propagator_gene;
+                let candidates = [&first.1, &second.1].map(|parent| {
+                    parent.find_connection(conn.from, conn.to).map(|i| &parent.connections()[i])
+                });
+                assert!(
+                    candidates.iter().flatten().any(|other| other.propagator_gene == *weight),
+                    "{weight:?} was not inherited"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn distance_counts_structural_differences() {
+        let mut rng = Random::seed_from_u64(3);
+        let config = TestConfig { gene_coefficient: 0.0, ..Default::default() };
+        let first = TestNeat::minimal(TestPhenotype, [(); 2], [(); 1], &mut rng, &config);
+        let distance = |a: &(TestNeat, _, _), b: &(TestNeat, _, _)| {
+            TestNeat::distance((&a.0, &a.1, &a.2), (&b.0, &b.1, &b.2), &config)
+        };
+        assert_eq!(0.0, distance(&first, &first));
+        let mut second = first.clone();
+        second.0.add_neuron(&mut second.1, &mut rng, &config);
+        let registered = config.innovations.registry().next;
+        // NOTE: one disjoint connection and two excess connections
+        assert_eq!(1.0, distance(&first, &second));
+        assert_eq!(distance(&first, &second), distance(&second, &first));
+        assert_eq!(registered, config.innovations.registry().next, "distance should be read-only");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn agent_deserialize_checks_body() {
+        let mut rng = Random::seed_from_u64(4);
+        let config = TestConfig::default();
+        let (genome, brain, body) =
+            TestNeat::minimal(TestPhenotype, [(); 3], [(); 1], &mut rng, &config);
+        let agent = Agent::new(genome, brain, body);
+        let json = serde_json::to_value(&agent).unwrap();
+        let copy = serde_json::from_value::<Agent<TestNeat, TestPhenotype>>(json.clone()).unwrap();
+        assert_eq!(0.0, agent.distance(&copy, &config));
+
+        let mut unsorted = json;
+        unsorted["body"]["sensors"].as_array_mut().unwrap().reverse();
+        let error = serde_json::from_value::<Agent<TestNeat, TestPhenotype>>(unsorted).unwrap_err();
+        assert!(error.to_string().contains("out of order"), "{error}");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn innovations_survive_serialization() {
+        let mut rng = Random::seed_from_u64(5);
+        let config = TestConfig::default();
+        let (genome, brain, _) =
+            TestNeat::minimal(TestPhenotype, [(); 2], [(); 1], &mut rng, &config);
+        let json = serde_json::to_string(&config.innovations).unwrap();
+        let restored = TestConfig {
+            innovations: serde_json::from_str(&json).unwrap(),
+            ..TestConfig::default()
+        };
+        for conn in brain.connections() {
+            assert_eq!(
+                genome.connection_innovation(conn, &config),
+                genome.connection_innovation(conn, &restored)
+            );
+        }
+        assert_eq!(config.innovations.fresh(), restored.innovations.fresh());
+    }
+}
@@ -59,4 +59,9 @@
 
 pub mod agent;
 mod arena;
+pub mod genome;
+pub mod numeric;
+pub mod random;
+#[cfg(test)]
+pub(crate) mod test_util;
 pub mod world;
@@ -1,5 +1,3 @@
-use evo_nn::*;
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Hello, world!");
     Ok(())
@@ -0,0 +1,185 @@
+use std::fmt::Display;
+
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::{agent::Activator, genome::neat::NeatGene};
+
+/// Activation function selected by an [`ActivationGene`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Function {
+    Identity,
+    Sigmoid,
+    Tanh,
+    Relu,
+    Gaussian,
+    Sine,
+    Step,
+}
+impl Function {
+    pub const ALL: [Self; 7] = [
+        Self::Identity,
+        Self::Sigmoid,
+        Self::Tanh,
+        Self::Relu,
+        Self::Gaussian,
+        Self::Sine,
+        Self::Step,
+    ];
+
+    pub fn apply(self, x: f64) -> f64 {
+        match self {
+            Self::Identity => x,
+            Self::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Self::Tanh => x.tanh(),
+            Self::Relu => x.max(0.0),
+            Self::Gaussian => (-x * x).exp(),
+            Self::Sine => x.sin(),
+            Self::Step =>
+                if x > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                },
+        }
+    }
+}
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Identity => "identity",
+            Self::Sigmoid => "sigmoid",
+            Self::Tanh => "tanh",
+            Self::Relu => "relu",
+            Self::Gaussian => "gaussian",
+            Self::Sine => "sine",
+            Self::Step => "step",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Applies `function` to the collected input shifted by `bias`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActivationGene {
+    pub function: Function,
+    pub bias:     f64,
+}
+impl Default for ActivationGene {
+    fn default() -> Self {
+        Self { function: Function::Tanh, bias: 0.0 }
+    }
+}
+impl Display for ActivationGene {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({:+})", self.function, self.bias)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActivationMutation {
+    /// Functions that can be picked by new and mutated genes.
+    pub functions:       Vec<Function>,
+    /// Chance that a mutation picks a new function.
+    pub function_chance: f64,
+    /// Largest change of the bias in a single mutation.
+    pub bias_power:      f64,
+}
+impl Default for ActivationMutation {
+    fn default() -> Self {
+        Self { functions: Function::ALL.to_vec(), function_chance: 0.1, bias_power: 0.5 }
+    }
+}
+
+impl NeatGene for ActivationGene {
+    type Config = ActivationMutation;
+
+    fn create(rng: &mut impl Rng, config: &Self::Config) -> Self {
+        let function = config.functions.choose(rng).copied().unwrap_or(Function::Tanh);
+        Self { function, bias: 0.0 }
+    }
+
+    fn mutate(&mut self, rng: &mut impl Rng, config: &Self::Config) {
+        if rng.random_bool(config.function_chance) {
+            self.function = config.functions.choose(rng).copied().unwrap_or(self.function);
+        }
+        if config.bias_power > 0.0 {
+            self.bias += rng.random_range(-config.bias_power..config.bias_power);
+        }
+    }
+
+    fn distance(&self, other: &Self, _config: &Self::Config) -> f64 {
+        let function = if self.function == other.function { 0.0 } else { 1.0 };
+        function + (self.bias - other.bias).abs()
+    }
+}
+
+/// Neuron state holding the output of the last activation.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Activation(pub f64);
+impl Activator for Activation {
+    type Config = ();
+    type Gene = ActivationGene;
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn activate(&mut self, input: Self::Input<'_>, gene: &Self::Gene, _config: &Self::Config) {
+        self.0 = gene.function.apply(input + gene.bias);
+    }
+
+    fn output(&self) -> Self::Output<'_> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{SeedableRng, rngs::SmallRng};
+
+    use super::*;
+
+    #[test]
+    fn functions_have_expected_values() {
+        let at = |function: Function, x: f64| function.apply(x);
+        assert_eq!(-2.0, at(Function::Identity, -2.0));
+        assert_eq!(0.5, at(Function::Sigmoid, 0.0));
+        assert_eq!(0.0, at(Function::Tanh, 0.0));
+        assert_eq!(0.0, at(Function::Relu, -1.0));
+        assert_eq!(3.0, at(Function::Relu, 3.0));
+        assert_eq!(1.0, at(Function::Gaussian, 0.0));
+        assert_eq!(0.0, at(Function::Sine, 0.0));
+        assert_eq!(0.0, at(Function::Step, 0.0));
+        assert_eq!(1.0, at(Function::Step, 0.1));
+        for function in Function::ALL {
+            for x in [-1e3, -1.0, 0.0, 1.0, 1e3] {
+                assert!(at(function, x).is_finite(), "{function} | {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn mutation_only_picks_allowed_functions() {
+        let config = ActivationMutation {
+            functions: vec![Function::Relu, Function::Sine],
+            function_chance: 1.0,
+            ..Default::default()
+        };
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut gene = ActivationGene::create(&mut rng, &config);
+        for _ in 0..32 {
+            assert!(config.functions.contains(&gene.function));
+            gene.mutate(&mut rng, &config);
+        }
+        let mut activation = Activation::default();
+        activation.activate(-1.0, &ActivationGene { function: Function::Relu, bias: 2.0 }, &());
+        assert_eq!(1.0, activation.output());
+    }
+}
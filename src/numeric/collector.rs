@@ -0,0 +1,150 @@
+use crate::agent::Collector;
+
+/// Adds all inputs.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sum(f64);
+impl Collector for Sum {
+    type Config = ();
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn push(&mut self, input: Self::Input<'_>, _config: &Self::Config) {
+        self.0 += input;
+    }
+
+    fn collect(&mut self, _config: &Self::Config) -> Self::Output<'_> {
+        self.0
+    }
+
+    fn clear(&mut self, _config: &Self::Config) {
+        self.0 = 0.0;
+    }
+}
+
+/// Multiplies all inputs, collects `0` without any inputs.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Product(Option<f64>);
+impl Collector for Product {
+    type Config = ();
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn push(&mut self, input: Self::Input<'_>, _config: &Self::Config) {
+        self.0 = Some(self.0.map_or(input, |product| product * input));
+    }
+
+    fn collect(&mut self, _config: &Self::Config) -> Self::Output<'_> {
+        self.0.unwrap_or_default()
+    }
+
+    fn clear(&mut self, _config: &Self::Config) {
+        self.0 = None;
+    }
+}
+
+/// Keeps the largest input, collects `0` without any inputs.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Max(Option<f64>);
+impl Collector for Max {
+    type Config = ();
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn push(&mut self, input: Self::Input<'_>, _config: &Self::Config) {
+        self.0 = Some(self.0.map_or(input, |max| max.max(input)));
+    }
+
+    fn collect(&mut self, _config: &Self::Config) -> Self::Output<'_> {
+        self.0.unwrap_or_default()
+    }
+
+    fn clear(&mut self, _config: &Self::Config) {
+        self.0 = None;
+    }
+}
+
+/// Averages all inputs, collects `0` without any inputs.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mean {
+    sum:   f64,
+    count: u32,
+}
+impl Collector for Mean {
+    type Config = ();
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn push(&mut self, input: Self::Input<'_>, _config: &Self::Config) {
+        self.sum += input;
+        self.count += 1;
+    }
+
+    fn collect(&mut self, _config: &Self::Config) -> Self::Output<'_> {
+        if self.count == 0 { 0.0 } else { self.sum / self.count as f64 }
+    }
+
+    fn clear(&mut self, _config: &Self::Config) {
+        self.sum = 0.0;
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    fn collect<C>(inputs: &[f64]) -> f64
+    where
+        C: 'static + for<'c> Collector<Config = (), Input<'c> = f64, Output<'c> = f64>,
+    {
+        let mut collector = C::default();
+        // NOTE: collectors are reused, so the first round must not influence the second one
+        for _ in 0..2 {
+            collector.clear(&());
+            inputs.iter().for_each(|input| collector.push(*input, &()));
+        }
+        collector.collect(&())
+    }
+
+    #[test]
+    fn collectors_combine_inputs() {
+        let inputs = [2.0, -1.0, 5.0];
+        assert_eq!(6.0, collect::<Sum>(&inputs));
+        assert_eq!(-10.0, collect::<Product>(&inputs));
+        assert_eq!(5.0, collect::<Max>(&inputs));
+        assert_eq!(2.0, collect::<Mean>(&inputs));
+        assert_eq!(0.0, collect::<Sum>(&[]));
+        assert_eq!(0.0, collect::<Product>(&[]));
+        assert_eq!(0.0, collect::<Max>(&[]));
+        assert_eq!(0.0, collect::<Mean>(&[]));
+    }
+}
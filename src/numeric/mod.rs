@@ -0,0 +1,29 @@
+//! Ready to use [`Activator`](crate::agent::Activator), [`Propagator`](crate::agent::Propagator)
+//! and [`Collector`](crate::agent::Collector) implementations passing plain `f64` values.
+//!
+//! All of them fit together, so any combination can be used as a [`Genome`](crate::agent::Genome)
+//! (e.g. `Neat<Activation, Weighted, Sum>`).
+
+mod activator;
+mod collector;
+mod propagator;
+
+pub use activator::*;
+pub use collector::*;
+pub use propagator::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{agent::Genome, genome::neat::Neat};
+
+    fn is_genome<G: Genome>() {}
+
+    #[test]
+    fn combinations_are_genomes() {
+        is_genome::<Neat<Activation, Weighted, Sum>>();
+        is_genome::<Neat<Activation, Weighted, Product>>();
+        is_genome::<Neat<Activation, Modulated, Max>>();
+        is_genome::<Neat<Activation, Modulated, Mean>>();
+    }
+}
@@ -0,0 +1,227 @@
+use std::{borrow::Borrow, collections::HashMap, fmt::Display};
+
+use rand::Rng;
+
+use crate::{
+    agent::{NeuronID, Propagator},
+    genome::neat::NeatGene,
+};
+
+#[derive(Debug, Clone)]
+pub struct WeightMutation {
+    /// New weights are picked from `-range..range`.
+    pub range:          f64,
+    /// Largest change of the weight in a single mutation.
+    pub power:          f64,
+    /// Chance that a mutation picks a completely new weight.
+    pub replace_chance: f64,
+}
+impl Default for WeightMutation {
+    fn default() -> Self {
+        Self { range: 1.0, power: 0.5, replace_chance: 0.1 }
+    }
+}
+impl WeightMutation {
+    fn create(&self, rng: &mut impl Rng) -> f64 {
+        if self.range > 0.0 { rng.random_range(-self.range..self.range) } else { 0.0 }
+    }
+
+    fn mutate(&self, weight: &mut f64, rng: &mut impl Rng) {
+        if rng.random_bool(self.replace_chance) {
+            *weight = self.create(rng);
+        } else if self.power > 0.0 {
+            *weight += rng.random_range(-self.power..self.power);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weight(pub f64);
+impl Display for Weight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl NeatGene for Weight {
+    type Config = WeightMutation;
+
+    fn create(rng: &mut impl Rng, config: &Self::Config) -> Self {
+        Self(config.create(rng))
+    }
+
+    fn mutate(&mut self, rng: &mut impl Rng, config: &Self::Config) {
+        config.mutate(&mut self.0, rng);
+    }
+
+    /// Keeps the signal unchanged by using weight `1` for the incoming connection.
+    fn split(self, _rng: &mut impl Rng, _config: &Self::Config) -> (Self, Self) {
+        (Self(1.0), self)
+    }
+
+    fn distance(&self, other: &Self, _config: &Self::Config) -> f64 {
+        (self.0 - other.0).abs()
+    }
+}
+
+/// Multiplies the input with a [`Weight`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weighted;
+impl Propagator for Weighted {
+    type Config = ();
+    type Gene = Weight;
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn modulation(
+        &self,
+        _gene: &Self::Gene,
+        _config: &Self::Config,
+    ) -> impl Iterator<Item: Borrow<NeuronID>> {
+        std::iter::empty::<NeuronID>()
+    }
+
+    fn propagate(
+        &mut self,
+        input: Self::Input<'_>,
+        _modulation: &[Self::Input<'_>],
+        gene: &Self::Gene,
+        _config: &Self::Config,
+    ) -> Self::Output<'_> {
+        input * gene.0
+    }
+
+    fn is_silent(gene: &Self::Gene) -> bool {
+        gene.0 == 0.0
+    }
+}
+
+/// [`Weight`] that is additionally scaled by the output of another neuron.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModulatedWeight {
+    pub weight:    f64,
+    pub modulator: Option<NeuronID>,
+}
+impl Display for ModulatedWeight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.modulator {
+            Some(modulator) => write!(f, "{} * {modulator}", self.weight),
+            None => write!(f, "{}", self.weight),
+        }
+    }
+}
+/// Only changes the weight, modulators have to be set by the [`Genome`](crate::agent::Genome)
+/// since genes don't know about other neurons.
+impl NeatGene for ModulatedWeight {
+    type Config = WeightMutation;
+
+    fn create(rng: &mut impl Rng, config: &Self::Config) -> Self {
+        Self { weight: config.create(rng), modulator: None }
+    }
+
+    fn mutate(&mut self, rng: &mut impl Rng, config: &Self::Config) {
+        config.mutate(&mut self.weight, rng);
+    }
+
+    /// Keeps the signal unchanged by using weight `1` for the incoming connection,
+    /// the modulation is applied to the outgoing connection only.
+    fn split(self, _rng: &mut impl Rng, _config: &Self::Config) -> (Self, Self) {
+        (Self { weight: 1.0, modulator: None }, self)
+    }
+
+    fn distance(&self, other: &Self, _config: &Self::Config) -> f64 {
+        let modulator = if self.modulator == other.modulator { 0.0 } else { 1.0 };
+        modulator + (self.weight - other.weight).abs()
+    }
+}
+
+/// Multiplies the input with a [`ModulatedWeight`].
+/// Unmodulated connections behave like [`Weighted`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Modulated;
+impl Propagator for Modulated {
+    type Config = ();
+    type Gene = ModulatedWeight;
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn modulation(
+        &self,
+        gene: &Self::Gene,
+        _config: &Self::Config,
+    ) -> impl Iterator<Item: Borrow<NeuronID>> {
+        gene.modulator.into_iter()
+    }
+
+    fn propagate(
+        &mut self,
+        input: Self::Input<'_>,
+        modulation: &[Self::Input<'_>],
+        gene: &Self::Gene,
+        _config: &Self::Config,
+    ) -> Self::Output<'_> {
+        input * gene.weight * modulation.iter().product::<f64>()
+    }
+
+    fn remap_gene(gene: &mut Self::Gene, map: &HashMap<NeuronID, NeuronID>) {
+        if let Some(modulator) = &mut gene.modulator {
+            *modulator = map.get(modulator).copied().unwrap_or(*modulator);
+        }
+    }
+
+    fn referenced_neurons(gene: &Self::Gene) -> impl Iterator<Item: Borrow<NeuronID>> {
+        gene.modulator.into_iter()
+    }
+
+    fn is_silent(gene: &Self::Gene) -> bool {
+        gene.weight == 0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{SeedableRng, rngs::SmallRng};
+
+    use super::*;
+
+    #[test]
+    fn modulation_scales_weight() {
+        let id = NeuronID::try_from(3).unwrap();
+        let mut propagator = Modulated;
+        let gene = ModulatedWeight { weight: 0.5, modulator: Some(id) };
+        let modulation = propagator.modulation(&gene, &()).map(|id| *id.borrow());
+        assert_eq!(vec![id], modulation.collect::<Vec<_>>());
+        assert_eq!(-3.0, propagator.propagate(4.0, &[-1.5], &gene, &()));
+        let gene = ModulatedWeight { weight: 0.5, modulator: None };
+        assert_eq!(2.0, propagator.propagate(4.0, &[], &gene, &()));
+        assert_eq!(2.0, Weighted.propagate(4.0, &[], &Weight(0.5), &()));
+    }
+
+    #[test]
+    fn split_keeps_signal() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let config = WeightMutation::default();
+        let (first, second) = Weight(0.25).split(&mut rng, &config);
+        let hidden = Weighted.propagate(1.0, &[], &first, &());
+        assert_eq!(0.25, Weighted.propagate(hidden, &[], &second, &()));
+        for _ in 0..32 {
+            let weight = Weight::create(&mut rng, &config);
+            assert!(weight.0.abs() < config.range);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use rand::{RngCore, SeedableRng, rand_core::impls::fill_bytes_via_next};
+
+/// Seedable source of randomness that can be split into independent streams.
+///
+/// Implements xoshiro256++, so the same seed gives the same results on every platform
+/// and with every version of `rand`.
+/// [`World`](crate::world::World) owns one and passes it to all genome and controller operations,
+/// so a run started from a fixed seed can be reproduced exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Random([u64; 4]);
+impl Random {
+    /// Creates a generator seeded by the operating system, the results are not reproducible.
+    pub fn from_entropy() -> Self {
+        Self::from_rng(&mut rand::rng())
+    }
+
+    /// Creates a new generator that is independent of this one.
+    /// Splitting in the same order always gives the same generators,
+    /// e.g. to hand a separate generator to each agent that is evaluated in parallel.
+    pub fn split(&mut self) -> Self {
+        Self::seed_from_u64(self.next_u64())
+    }
+}
+impl SeedableRng for Random {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 4];
+        for (state, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            *state = u64::from_le_bytes(bytes.try_into().expect("chunks have 8 bytes"));
+        }
+        // NOTE: xoshiro only produces zeros when starting from the all zero state
+        if state == [0; 4] {
+            state[0] = 0x9E37_79B9_7F4A_7C15;
+        }
+        Self(state)
+    }
+}
+impl RngCore for Random {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.0;
+        let result = s0.wrapping_add(*s3).rotate_left(23).wrapping_add(*s0);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        fill_bytes_via_next(self, dst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_values() {
+        let mut a = Random::seed_from_u64(7);
+        let mut b = Random::seed_from_u64(7);
+        let values = |rng: &mut Random| (0..16).map(|_| rng.random::<u64>()).collect::<Vec<_>>();
+        assert_eq!(values(&mut a), values(&mut b));
+        assert_ne!(values(&mut a), values(&mut Random::seed_from_u64(8)));
+        assert_ne!(0, Random::from_seed([0; 32]).next_u64());
+    }
+
+    #[test]
+    fn split_is_deterministic_and_independent() {
+        let mut a = Random::seed_from_u64(1);
+        let mut b = Random::seed_from_u64(1);
+        let (mut first, mut second) = (a.split(), a.split());
+        assert_eq!(first, b.split());
+        assert_eq!(second, b.split());
+        assert_ne!(first.next_u64(), second.next_u64());
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,129 @@
+//! Minimal implementations of the agent traits shared by the tests of multiple modules.
+
+use std::borrow::Borrow;
+
+pub(crate) use crate::numeric::Sum;
+use crate::{agent::*, random::Random};
+
+/// Neuron state passing the collected input through unchanged.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Value(pub f64);
+impl Activator for Value {
+    type Config = ();
+    type Gene = ();
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn activate(&mut self, input: Self::Input<'_>, _gene: &Self::Gene, _config: &Self::Config) {
+        self.0 = input;
+    }
+
+    fn output(&self) -> Self::Output<'_> {
+        self.0
+    }
+}
+
+/// Connection passing its input through unchanged.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Identity;
+impl Propagator for Identity {
+    type Config = ();
+    type Gene = ();
+    type Input<'i>
+        = f64
+    where
+        Self: 'i;
+    type Output<'o>
+        = f64
+    where
+        Self: 'o;
+
+    fn modulation(
+        &self,
+        _gene: &Self::Gene,
+        _config: &Self::Config,
+    ) -> impl Iterator<Item: Borrow<NeuronID>> {
+        std::iter::empty::<NeuronID>()
+    }
+
+    fn propagate(
+        &mut self,
+        input: Self::Input<'_>,
+        _modulation: &[Self::Input<'_>],
+        _gene: &Self::Gene,
+        _config: &Self::Config,
+    ) -> Self::Output<'_> {
+        input
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TestPhenotype;
+impl Phenotype for TestPhenotype {
+    type ActionGene = ();
+    type SensorGene = ();
+}
+
+/// Sensor value that is only convertible by reference.
+#[derive(Debug, Default)]
+pub(crate) struct Reading(pub f64);
+impl From<&Reading> for f64 {
+    fn from(value: &Reading) -> Self {
+        value.0
+    }
+}
+
+/// Genome that is only a position on a line, children are copies of their primary parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Label(pub f64);
+impl Genome for Label {
+    type Activator = Value;
+    type Collector = Sum;
+    type Config = ();
+    type Propagator = Identity;
+
+    fn populate<P: Phenotype>(
+        parents: impl IntoIterator<Item = (Self, Brain<Value, Identity>, Body<P>)>,
+        parent_count: usize,
+        children_count: usize,
+        _rng: &mut Random,
+        _config: &Self::Config,
+    ) -> impl Iterator<Item = (Self, Brain<Value, Identity>, Body<P>)> {
+        let parents = parents.into_iter().take(parent_count).collect::<Vec<_>>();
+        let children = if parents.is_empty() { 0 } else { children_count };
+        (0..children).map(move |i| parents[i % parents.len()].clone())
+    }
+
+    fn distance<P: Phenotype>(
+        a: (&Self, &Brain<Value, Identity>, &Body<P>),
+        b: (&Self, &Brain<Value, Identity>, &Body<P>),
+        _config: &Self::Config,
+    ) -> f64 {
+        (a.0.0 - b.0.0).abs()
+    }
+
+    fn spawn<'a, P, I>(
+        parents: I,
+        _count: usize,
+        _rng: &mut Random,
+        _config: &Self::Config,
+    ) -> (Self, Brain<Value, Identity>, Body<P>)
+    where
+        P: 'a + Phenotype,
+        Self: 'a,
+        I: IntoIterator<Item = (&'a Self, &'a Brain<Value, Identity>, &'a Body<P>)>,
+    {
+        let (genome, brain, body) =
+            parents.into_iter().next().expect("at least one parent is required");
+        (*genome, brain.clone(), body.clone())
+    }
+}
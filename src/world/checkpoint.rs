@@ -0,0 +1,190 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    /// The checkpoint could not be encoded or decoded, this includes failed validation of agents.
+    Format(serde_json::Error),
+    /// The brain state of the agent at this index does not match its [`Brain`].
+    State(usize),
+}
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "checkpoint io failed: {error}"),
+            Self::Format(error) => write!(f, "invalid checkpoint: {error}"),
+            Self::State(index) => write!(f, "state of agent {index} does not match its brain"),
+        }
+    }
+}
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Format(error) => Some(error),
+            Self::State(_) => None,
+        }
+    }
+}
+impl From<io::Error> for CheckpointError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+impl From<serde_json::Error> for CheckpointError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Format(value)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(bound(serialize = "Agent<G, C::Phenotype>: Serialize, State<G, C>: Serialize, \
+                           C: Serialize, S: Serialize"))]
+struct CheckpointRef<'w, G, C, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+{
+    agents:     &'w [Agent<G, C::Phenotype>],
+    state:      &'w [State<G, C>],
+    controller: &'w C,
+    store:      &'w S,
+    rng:        &'w Random,
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "Agent<G, C::Phenotype>: Deserialize<'de>, \
+                             StateSnapshot<G::Activator, G::Propagator, G::Collector>: \
+                             Deserialize<'de>, C::State: Deserialize<'de>, \
+                             C: Deserialize<'de>, S: Deserialize<'de>"))]
+struct Checkpoint<G, C, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+{
+    agents:     Vec<Agent<G, C::Phenotype>>,
+    state:      Vec<StateData<G, C>>,
+    controller: C,
+    store:      S,
+    rng:        Random,
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "StateSnapshot<G::Activator, G::Propagator, G::Collector>: \
+                             Deserialize<'de>, C::State: Deserialize<'de>"))]
+struct StateData<G, C>
+where
+    G: 'static + Genome,
+    C: Controller,
+{
+    brain: StateSnapshot<G::Activator, G::Propagator, G::Collector>,
+    body:  C::State,
+}
+
+impl<G, C, S, O> World<G, C, S, O>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    G: 'static + Genome,
+    C: Controller,
+    S: AgentStore<G, C>,
+    O: Observer<G, C, S>,
+    for<'c> <G::Collector as Collector>::Input<'c>: From<&'c C::SensorOutput>,
+    for<'p> C::ActionInput: From<<G::Propagator as Propagator>::Input<'p>>,
+{
+    /// Writes agents, their runtime state, the controller, the store and the [`Random`] as JSON.
+    ///
+    /// Configs are not part of the checkpoint and have to be kept by the caller, the same goes for
+    /// statistics, [`World::with_convergence`] and the [`Observer`]
+    /// (e.g. [`neat::Innovations`](crate::genome::neat::Innovations) are required to resume a NEAT run).
+    pub fn write_checkpoint(&self, writer: impl Write) -> Result<(), CheckpointError>
+    where
+        Agent<G, C::Phenotype>: Serialize,
+        State<G, C>: Serialize,
+        C: Serialize,
+        S: Serialize,
+    {
+        let checkpoint = CheckpointRef {
+            agents:     &self.agents,
+            state:      &self.state,
+            controller: &self.controller,
+            store:      &self.store,
+            rng:        &self.rng,
+        };
+        Ok(serde_json::to_writer(writer, &checkpoint)?)
+    }
+
+    /// Writes a checkpoint to the file at `path` (see [`World::write_checkpoint`]).
+    pub fn checkpoint(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError>
+    where
+        Agent<G, C::Phenotype>: Serialize,
+        State<G, C>: Serialize,
+        C: Serialize,
+        S: Serialize,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_checkpoint(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+}
+
+impl<G, C, S> World<G, C, S>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    G: 'static + Genome,
+    C: Controller,
+    S: AgentStore<G, C>,
+    for<'c> <G::Collector as Collector>::Input<'c>: From<&'c C::SensorOutput>,
+    for<'p> C::ActionInput: From<<G::Propagator as Propagator>::Input<'p>>,
+{
+    /// Recreates a [`World`] from a checkpoint created by [`World::write_checkpoint`].
+    /// Calling [`World::step`] on the result continues exactly where the original world stopped,
+    /// an observer can be attached again using [`World::with_observer`]
+    /// (the same goes for [`World::with_convergence`]).
+    pub fn read_checkpoint(reader: impl Read) -> Result<Self, CheckpointError>
+    where
+        Agent<G, C::Phenotype>: for<'de> Deserialize<'de>,
+        StateSnapshot<G::Activator, G::Propagator, G::Collector>: for<'de> Deserialize<'de>,
+        C::State: for<'de> Deserialize<'de>,
+        C: for<'de> Deserialize<'de>,
+        S: for<'de> Deserialize<'de>,
+    {
+        let checkpoint: Checkpoint<G, C, S> = serde_json::from_reader(reader)?;
+        let (agents, states) = (checkpoint.agents, checkpoint.state);
+        if agents.len() != states.len() {
+            return Err(CheckpointError::State(agents.len().min(states.len())));
+        }
+        let mut world = Self::with_rng(checkpoint.controller, checkpoint.rng);
+        world.store = checkpoint.store;
+        for (index, (agent, state)) in agents.into_iter().zip(states).enumerate() {
+            let (brain, body, arena) = (agent.brain(), agent.body(), &mut world.arena);
+            // SAFETY: all states of a world live in its arena (see `World::initialize`)
+            let brain = unsafe { agent::State::from_snapshot(state.brain, brain, body, arena) }
+                .ok_or(CheckpointError::State(index))?;
+            world.agents.push(agent);
+            world.state.push(State { brain, body: state.body });
+        }
+        Ok(world)
+    }
+
+    /// Reads a checkpoint from the file at `path` (see [`World::read_checkpoint`]).
+    pub fn restore(path: impl AsRef<Path>) -> Result<Self, CheckpointError>
+    where
+        Agent<G, C::Phenotype>: for<'de> Deserialize<'de>,
+        StateSnapshot<G::Activator, G::Propagator, G::Collector>: for<'de> Deserialize<'de>,
+        C::State: for<'de> Deserialize<'de>,
+        C: for<'de> Deserialize<'de>,
+        S: for<'de> Deserialize<'de>,
+    {
+        Self::read_checkpoint(BufReader::new(File::open(path)?))
+    }
+}
@@ -3,7 +3,7 @@ use std::{borrow::Borrow, fmt::Debug};
 use super::*;
 
 /// Marker trait for [`Controller`]s that treat each agent seperate without interaction.
-/// This enables parallel processing of agents (see [`World::cylce_par`]).
+/// This enables parallel processing of agents (see [`World::cycle_par`]).
 /// # Safety
 /// Types implementing this should not implement [`Controller::step`].
 pub unsafe trait NoGlobalStep: Controller {}
@@ -24,36 +24,56 @@ where
     Kill(usize),
 }
 
+/// Environment the agents of a [`World`] live in.
+///
+/// All randomness should be drawn from the passed [`Random`], so runs are reproducible
+/// when the world was created with [`World::with_rng`].
 pub trait Controller: Debug {
     type Phenotype: Phenotype;
     type State: Debug;
-    type SensorOutput;
-    type ActionInput;
+    type SensorOutput: Default;
+    type ActionInput: Default;
     type Score;
     type SpawnHelper;
     type ParentIter: ExactSizeIterator<Item: Borrow<usize>>;
     type Config: Debug + Default;
 
-    fn initial_state(&self, phenotype: &Self::Phenotype, config: &Self::Config) -> Self::State;
+    fn initial_state(
+        &self,
+        phenotype: &Self::Phenotype,
+        rng: &mut Random,
+        config: &Self::Config,
+    ) -> Self::State;
     fn create_state(
         &self,
         phenotype: &Self::Phenotype,
         init: Self::SpawnHelper,
+        rng: &mut Random,
         config: &Self::Config,
     ) -> Self::State;
 
+    /// Writes the value of every sensor of the agent owning `state` into `outputs`.
     fn read_sensors<'s>(
         &self,
+        state: &Self::State,
         sensors: impl IntoIterator<Item = &'s <Self::Phenotype as Phenotype>::SensorGene>,
         outputs: &mut [Self::SensorOutput],
+        rng: &mut Random,
         config: &Self::Config,
     ) where
         Self::Phenotype: 's;
 
+    /// Applies `inputs` to the agent owning `state`.
+    /// Returns the final score when the agent is done.
+    ///
+    /// This only receives shared access to `self`, so that agents can be processed in parallel,
+    /// changes to the world should either be stored in `state` or be applied in [`Controller::step`].
     fn perform_actions<'a>(
-        &mut self,
+        &self,
+        state: &mut Self::State,
         actions: impl IntoIterator<Item = &'a <Self::Phenotype as Phenotype>::ActionGene>,
         inputs: &[Self::ActionInput],
+        rng: &mut Random,
         config: &Self::Config,
     ) -> Option<Self::Score>
     where
@@ -66,6 +86,7 @@ pub trait Controller: Debug {
         &mut self,
         agents: &[Agent<G, Self::Phenotype>],
         issue_command: impl FnMut(Command<Self>),
+        rng: &mut Random,
         config: &Self::Config,
     ) -> Option<()>
     where
@@ -1,16 +1,28 @@
-use std::{borrow::Borrow, fmt::Debug};
+use std::{borrow::Borrow, fmt::Debug, sync::Mutex};
 
 use crate::{
     agent::{self, *},
     arena::Arena,
+    random::Random,
 };
 
+#[cfg(feature = "json")]
+mod checkpoint;
 mod controller;
+mod observer;
+pub mod species;
+pub mod stats;
+pub mod store;
 
+#[cfg(feature = "json")]
+pub use checkpoint::*;
 pub use controller::*;
+pub use observer::*;
 
 #[expect(type_alias_bounds)]
 type StoreRef<'s, G, C: Controller, S> = (&'s Agent<G, C::Phenotype>, &'s S);
+/// Compares the outputs of two ticks, see [`Difference`].
+type DifferenceFn<T> = fn(&T, &T) -> f64;
 
 pub trait AgentStore<G, C>: Debug + Default
 where
@@ -21,7 +33,7 @@ where
     type Score: From<C::Score>;
     type Config: Debug + Default;
 
-    fn insert(&mut self, agent: Agent<G, C::Phenotype>, score: Self::Score);
+    fn insert(&mut self, agent: Agent<G, C::Phenotype>, score: Self::Score, config: &Self::Config);
     fn len(&self) -> usize;
     fn best(&self, config: &Self::Config) -> Option<StoreRef<G, C, Self::Score>>;
     fn drain(&mut self) -> impl Iterator<Item = (Agent<G, C::Phenotype>, Self::Score)>;
@@ -33,16 +45,22 @@ where
     fn populate(
         &mut self,
         count: usize,
+        rng: &mut Random,
         config: (&Self::Config, &G::Config),
     ) -> impl Iterator<Item = Agent<G, C::Phenotype>> {
         let len = self.len();
+        let parents = self.drain().map(|(agent, _)| agent);
         // SAFETY: `len` is always the count of items returned by `drain`
-        unsafe {
-            Agent::populate_unchecked(self.drain().map(|(agent, _)| agent), len, count, config.1)
-        }
+        unsafe { Agent::populate_unchecked(parents, len, count, rng, config.1) }
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "agent::State<G::Activator, G::Propagator, G::Collector>: \
+                             serde::Serialize, C::State: serde::Serialize"))
+)]
 pub struct State<G, C>
 where
     G: 'static + Genome,
@@ -62,7 +80,7 @@ where
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Config<G, C, S>
 where
     G: 'static + Genome,
@@ -76,6 +94,23 @@ where
     pub world_size: u32,
 }
 
+impl<G, C, S> Default for Config<G, C, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+    S: AgentStore<G, C>,
+{
+    fn default() -> Self {
+        Self {
+            brain:      Default::default(),
+            body:       Default::default(),
+            genome:     Default::default(),
+            store:      Default::default(),
+            world_size: Default::default(),
+        }
+    }
+}
+
 impl<G, C, S> Clone for Config<G, C, S>
 where
     G: 'static + Genome<Config: Clone>,
@@ -96,14 +131,13 @@ where
     }
 }
 
-pub struct World<G, C, S>
+pub struct World<G, C, S, O = ()>
 where
     // NOTE: `'static` bound is required by generic associated types at the moment
     G: 'static + Genome,
     C: Controller,
     S: AgentStore<G, C>,
 {
-    arena:          [Arena; 2],
     agents:         Vec<Agent<G, C::Phenotype>>,
     state:          Vec<State<G, C>>,
     sensor_buffer:  Vec<C::SensorOutput>,
@@ -111,6 +145,12 @@ where
     command_buffer: Vec<Command<C>>,
     controller:     C,
     store:          S,
+    rng:            Random,
+    stats:          Option<stats::Statistics<C::Score>>,
+    observer:       O,
+    difference:     Option<DifferenceFn<C::ActionInput>>,
+    // NOTE: has to be dropped after `state`
+    arena:          Arena,
 }
 
 impl<G, C, S> World<G, C, S>
@@ -122,9 +162,15 @@ where
     for<'c> <G::Collector as Collector>::Input<'c>: From<&'c C::SensorOutput>,
     for<'p> C::ActionInput: From<<G::Propagator as Propagator>::Input<'p>>,
 {
+    /// Creates a [`World`] with a randomly seeded [`Random`], see [`World::with_rng`].
     pub fn new(controller: C) -> Self {
+        Self::with_rng(controller, Random::from_entropy())
+    }
+
+    /// Creates a [`World`] that uses `rng` for all random decisions,
+    /// so runs started with the same seed give the same results.
+    pub fn with_rng(controller: C, rng: Random) -> Self {
         Self {
-            arena: [Arena::new(), Arena::new()],
             agents: Vec::new(),
             state: Vec::new(),
             sensor_buffer: Vec::new(),
@@ -132,18 +178,75 @@ where
             command_buffer: Vec::new(),
             controller,
             store: S::default(),
+            rng,
+            stats: None,
+            observer: (),
+            difference: None,
+            arena: Arena::new(),
+        }
+    }
+}
+
+impl<G, C, S, O> World<G, C, S, O>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    G: 'static + Genome,
+    C: Controller,
+    S: AgentStore<G, C>,
+    O: Observer<G, C, S>,
+    for<'c> <G::Collector as Collector>::Input<'c>: From<&'c C::SensorOutput>,
+    for<'p> C::ActionInput: From<<G::Propagator as Propagator>::Input<'p>>,
+{
+    /// Replaces the [`Observer`] that receives all events of this world.
+    pub fn with_observer<T: Observer<G, C, S>>(self, observer: T) -> World<G, C, S, T> {
+        World {
+            agents: self.agents,
+            state: self.state,
+            sensor_buffer: self.sensor_buffer,
+            action_buffer: self.action_buffer,
+            command_buffer: self.command_buffer,
+            controller: self.controller,
+            store: self.store,
+            rng: self.rng,
+            stats: self.stats,
+            observer,
+            difference: self.difference,
+            arena: self.arena,
         }
     }
 
+    /// Used in place of [`Difference`] until [`World::with_convergence`] is called.
+    /// Outputs never converge, so [`agent::Settle::Converge`] always runs `max_ticks` ticks.
+    fn missing_difference(_: &C::ActionInput, _: &C::ActionInput) -> f64 {
+        f64::INFINITY
+    }
+
+    /// Records [`stats::CycleStats`] for every cycle, using `fitness` to summarize scores.
+    /// Statistics are not part of checkpoints.
+    pub fn with_statistics(self, fitness: fn(&C::Score) -> f64) -> Self {
+        Self { stats: Some(stats::Statistics::new(fitness)), ..self }
+    }
+
     pub fn initialize(&mut self, config: &Config<G, C, S>) {
         let len = self.agents.len();
-        self.agents.extend(
-            self.store.populate(config.world_size as usize, (&config.store, &config.genome)),
-        );
+        let count = config.world_size as usize;
+        let store_config = (&config.store, &config.genome);
+        self.agents.extend(self.store.populate(count, &mut self.rng, store_config));
+        // SAFETY: states are freed before the arena is freed (see `World::remove`)
+        // and dropped before the arena is dropped (see the field order of `World`)
         self.state.extend(self.agents[len..].iter().map(|agent| State {
-            brain: agent::State::create_for(agent.brain(), agent.body(), &mut self.arena[0]),
-            body:  self.controller.initial_state(agent.body().phenotype(), &config.body),
-        }))
+            brain: unsafe {
+                agent::State::create_for(agent.brain(), agent.body(), &mut self.arena)
+            },
+            body:  self.controller.initial_state(
+                agent.body().phenotype(),
+                &mut self.rng,
+                &config.body,
+            ),
+        }));
+        for (index, (agent, state)) in self.agents.iter().zip(&self.state).enumerate().skip(len) {
+            self.observer.agent_created(index, agent, &state.body);
+        }
     }
 
     pub fn step(&mut self, config: &Config<G, C, S>) -> Option<()> {
@@ -153,84 +256,573 @@ where
             let agent = unsafe { self.agents.get_unchecked(i) };
             // SAFETY: state has always the same length as agents
             let state = unsafe { self.state.get_unchecked_mut(i) };
+            self.sensor_buffer.resize_with(agent.body().sensor_count(), Default::default);
+            self.action_buffer.resize_with(agent.body().action_count(), Default::default);
             self.controller.read_sensors(
+                &state.body,
                 agent.body().iter_sensors(),
                 &mut self.sensor_buffer,
+                &mut self.rng,
                 &config.body,
             );
-            state.brain.step(
+            self.observer.sensors_read(i, agent, &self.sensor_buffer);
+            state.brain.settle_by(
                 agent.brain(),
                 &self.sensor_buffer,
                 &mut self.action_buffer,
                 &config.brain,
+                self.difference.unwrap_or(Self::missing_difference),
             );
-            if let Some(score) = self.controller.perform_actions(
+            let score = self.controller.perform_actions(
+                &mut state.body,
                 agent.body().iter_actions(),
                 &self.action_buffer,
+                &mut self.rng,
                 &config.body,
-            ) {
-                let agent = self.agents.swap_remove(i);
-                self.store.insert(agent, score.into());
-                self.state.swap_remove(i);
+            );
+            self.observer.actions_performed(i, agent, &self.action_buffer, &state.body);
+            if let Some(score) = score {
+                if let Some(stats) = &mut self.stats {
+                    stats.record_score(&score, agent.brain());
+                }
+                self.observer.score_assigned(i, agent, &score);
+                let agent = self.remove(i);
+                self.store.insert(agent, score.into(), &config.store);
             } else {
                 i += 1;
             }
         }
-        self.controller.step(&self.agents, |cmd| self.command_buffer.push(cmd), &config.body)?;
-        for cmd in self.command_buffer.drain(..) {
+        if self.observer.should_stop() {
+            return None;
+        }
+        self.controller.step(
+            &self.agents,
+            |cmd| self.command_buffer.push(cmd),
+            &mut self.rng,
+            &config.body,
+        )?;
+        // NOTE: taken out temporarily to allow mutable access to `self` while keeping its memory
+        let mut commands = std::mem::take(&mut self.command_buffer);
+        for cmd in commands.drain(..) {
             match cmd {
                 Command::Spawn { parents, init } => {
                     let agent = Agent::spawn(
                         parents.map(|i| self.agents.get(*i.borrow()).expect("valid agent index")),
+                        &mut self.rng,
                         &config.genome,
                     );
                     let state = State {
-                        brain: agent::State::create_for(
-                            agent.brain(),
-                            agent.body(),
-                            &mut self.arena[0],
-                        ),
+                        // SAFETY: see `World::initialize`
+                        brain: unsafe {
+                            agent::State::create_for(agent.brain(), agent.body(), &mut self.arena)
+                        },
                         body:  self.controller.create_state(
                             agent.body().phenotype(),
                             init,
+                            &mut self.rng,
                             &config.body,
                         ),
                     };
+                    self.observer.spawned(self.agents.len(), &agent, &state.body);
                     self.agents.push(agent);
                     self.state.push(state);
+                    if let Some(stats) = &mut self.stats {
+                        stats.record_spawn();
+                    }
                 },
                 Command::Kill(index) => {
-                    self.agents.swap_remove(index);
-                    self.state.swap_remove(index);
+                    let agent = self.remove(index);
+                    self.observer.killed(index, &agent);
+                    if let Some(stats) = &mut self.stats {
+                        stats.record_kill();
+                    }
                 },
             }
         }
+        self.command_buffer = commands;
         Some(())
     }
 
+    /// Removes an agent and returns the memory of its brain state to the arena.
+    fn remove(&mut self, index: usize) -> Agent<G, C::Phenotype> {
+        let state = self.state.swap_remove(index);
+        // SAFETY: all states are created in `self.arena`
+        unsafe { state.brain.free_buffers(&mut self.arena) };
+        self.agents.swap_remove(index)
+    }
+
     pub fn finalize(&mut self, config: &Config<G, C, S>) -> Option<StoreRef<G, C, S::Score>> {
-        // TODO: consider using an allocator that can reuse memory if this is not good enough
-        self.state.iter_mut().for_each(|state| state.brain.move_buffers(&mut self.arena[1]));
-        // SAFETY: all data was moved and arena is empty here
-        unsafe {
-            self.arena[0].free_all();
+        // NOTE: memory of removed agents is reused by new spawns through the arena's free list,
+        // so alive agents never have to be moved; the arena is only reset when it is unused
+        if self.state.is_empty() {
+            // SAFETY: there are no states left that could reference the arena
+            unsafe { self.arena.free_all() };
         }
-        self.arena.swap(0, 1);
-        self.store.best(&config.store)
+        if let Some(stats) = &mut self.stats {
+            stats.finish_cycle();
+        }
+        let best = self.store.best(&config.store);
+        self.observer.cycle_end(best);
+        best
     }
 
-    pub fn cycle_par(&mut self, config: &Config<G, C, S>)
+    /// Runs a whole cycle with every agent evaluated independently on all available threads.
+    ///
+    /// Populates the world like [`World::initialize`] and runs each agent until
+    /// [`Controller::perform_actions`] returns a score, then inserts all agents into the store
+    /// and returns the result of [`World::finalize`].
+    /// Agents that are still alive from previous calls to [`World::step`] keep their controller state
+    /// but start with a fresh brain state.
+    pub fn cycle_par(&mut self, config: &Config<G, C, S>) -> Option<StoreRef<G, C, S::Score>>
     where
         C: Sync + NoGlobalStep,
+        C::State: Send,
+        C::Score: Send,
+        Agent<G, C::Phenotype>: Sync,
+        Config<G, C, S>: Sync,
     {
-        self.initialize(config);
-        // TODO: run each agent in own task returning final score
-        //   each agent can have their own fixed sized sensor/action buffer
-        // TODO: batch add all agents to store
-        self.finalize(config);
+        let len = self.agents.len();
+        let count = config.world_size as usize;
+        let store_config = (&config.store, &config.genome);
+        self.agents.extend(self.store.populate(count, &mut self.rng, store_config));
+        let mut bodies = Vec::with_capacity(self.agents.len());
+        for state in self.state.drain(..) {
+            // SAFETY: all states are created in `self.arena`
+            unsafe { state.brain.free_buffers(&mut self.arena) };
+            bodies.push(state.body);
+        }
+        bodies.extend(self.agents[len..].iter().map(|agent| {
+            self.controller.initial_state(agent.body().phenotype(), &mut self.rng, &config.body)
+        }));
+        for (index, (agent, body)) in self.agents.iter().zip(&bodies).enumerate().skip(len) {
+            self.observer.agent_created(index, agent, body);
+        }
+        // NOTE: generators are split in agent order, so results don't depend on thread scheduling
+        let mut rngs = self.agents.iter().map(|_| self.rng.split()).collect::<Vec<_>>();
+        let mut scores = Vec::new();
+        scores.resize_with(self.agents.len(), || None);
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = self.agents.len().div_ceil(threads * 4).max(1);
+        let chunks = Mutex::new(
+            self.agents
+                .chunks(chunk_size)
+                .zip(bodies.chunks_mut(chunk_size))
+                .zip(rngs.chunks_mut(chunk_size))
+                .zip(scores.chunks_mut(chunk_size)),
+        );
+        let controller = &self.controller;
+        let difference = self.difference.unwrap_or(Self::missing_difference);
+        std::thread::scope(|scope| {
+            for _ in 0..threads.min(self.agents.len()) {
+                scope.spawn(|| {
+                    let mut arena = Arena::new();
+                    let mut sensors = Vec::new();
+                    let mut actions = Vec::new();
+                    loop {
+                        // NOTE: the lock has to be released before evaluating the chunk
+                        let next = chunks.lock().expect("no worker should panic").next();
+                        let Some((((agents, bodies), rngs), scores)) = next else {
+                            break;
+                        };
+                        let agents = agents.iter().zip(bodies).zip(rngs).zip(scores);
+                        for (((agent, body), rng), score) in agents {
+                            *score = Some(Self::evaluate(
+                                (controller, difference),
+                                agent,
+                                (body, rng),
+                                (&mut arena, &mut sensors, &mut actions),
+                                config,
+                            ));
+                        }
+                    }
+                });
+            }
+        });
+        for (i, (agent, score)) in self.agents.drain(..).zip(scores).enumerate() {
+            let score = score.expect("all agents should be evaluated");
+            if let Some(stats) = &mut self.stats {
+                stats.record_score(&score, agent.brain());
+            }
+            self.observer.score_assigned(i, &agent, &score);
+            self.store.insert(agent, score.into(), &config.store);
+        }
+        self.finalize(config)
+    }
+
+    /// Runs a single agent until it returns a score using private buffers.
+    fn evaluate(
+        (controller, difference): (&C, DifferenceFn<C::ActionInput>),
+        agent: &Agent<G, C::Phenotype>,
+        state: (&mut C::State, &mut Random),
+        buffers: (&mut Arena, &mut Vec<C::SensorOutput>, &mut Vec<C::ActionInput>),
+        config: &Config<G, C, S>,
+    ) -> C::Score {
+        let (body, rng) = state;
+        let (arena, sensors, actions) = buffers;
+        sensors.resize_with(agent.body().sensor_count(), Default::default);
+        actions.resize_with(agent.body().action_count(), Default::default);
+        // SAFETY: `brain` is dropped before `arena` is freed below
+        let mut brain = unsafe { agent::State::create_for(agent.brain(), agent.body(), arena) };
+        let score = loop {
+            let interface = agent.body();
+            controller.read_sensors(body, interface.iter_sensors(), sensors, rng, &config.body);
+            brain.settle_by(agent.brain(), sensors, actions, &config.brain, difference);
+            if let Some(score) = controller.perform_actions(
+                body,
+                interface.iter_actions(),
+                actions,
+                rng,
+                &config.body,
+            ) {
+                break score;
+            }
+        };
+        drop(brain);
+        // SAFETY: the only state allocated in `arena` was dropped above
+        unsafe { arena.free_all() };
+        score
     }
 
     pub fn agents(&self) -> &[Agent<G, C::Phenotype>] {
         &self.agents
     }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Gives access to the [`AgentStore`], e.g. to insert initial agents before the first cycle.
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Statistics of all finished cycles, if enabled by [`World::with_statistics`].
+    pub fn statistics(&self) -> Option<&stats::Statistics<C::Score>> {
+        self.stats.as_ref()
+    }
+
+    /// Gives access to the statistics, e.g. to record the number of species.
+    pub fn statistics_mut(&mut self) -> Option<&mut stats::Statistics<C::Score>> {
+        self.stats.as_mut()
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Gives access to the [`Random`] of this world, e.g. to create initial agents.
+    pub fn rng_mut(&mut self) -> &mut Random {
+        &mut self.rng
+    }
+}
+
+impl<G, C, S, O> World<G, C, S, O>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    G: 'static + Genome,
+    C: Controller<ActionInput: Difference>,
+    S: AgentStore<G, C>,
+    O: Observer<G, C, S>,
+    for<'c> <G::Collector as Collector>::Input<'c>: From<&'c C::SensorOutput>,
+    for<'p> C::ActionInput: From<<G::Propagator as Propagator>::Input<'p>>,
+{
+    /// Compares the actions of consecutive ticks using [`Difference`],
+    /// so [`Settle::Converge`] in [`Config::brain`] can stop before `max_ticks`.
+    /// This is not part of checkpoints.
+    pub fn with_convergence(self) -> Self {
+        Self { difference: Some(C::ActionInput::difference), ..self }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        genome::neat::{self, Neat},
+        test_util::*,
+    };
+
+    /// Feeds the number of performed steps to every agent and ends after `0` steps.
+    #[derive(Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Counter(u32);
+    impl Controller for Counter {
+        type ActionInput = f64;
+        type Config = ();
+        type ParentIter = std::iter::Empty<usize>;
+        type Phenotype = TestPhenotype;
+        type Score = f64;
+        type SensorOutput = Reading;
+        type SpawnHelper = ();
+        type State = u32;
+
+        fn initial_state(
+            &self,
+            _phenotype: &Self::Phenotype,
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) -> u32 {
+            0
+        }
+
+        fn create_state(
+            &self,
+            _phenotype: &Self::Phenotype,
+            _init: Self::SpawnHelper,
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) -> u32 {
+            0
+        }
+
+        fn read_sensors<'s>(
+            &self,
+            state: &Self::State,
+            _sensors: impl IntoIterator<Item = &'s ()>,
+            outputs: &mut [Self::SensorOutput],
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) {
+            outputs.iter_mut().for_each(|output| *output = Reading(*state as f64));
+        }
+
+        fn perform_actions<'a>(
+            &self,
+            state: &mut Self::State,
+            _actions: impl IntoIterator<Item = &'a ()>,
+            inputs: &[Self::ActionInput],
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) -> Option<Self::Score> {
+            *state += 1;
+            (*state == self.0).then(|| inputs.iter().sum())
+        }
+    }
+    // SAFETY: `Counter` does not implement `Controller::step`
+    unsafe impl NoGlobalStep for Counter {}
+
+    type TestGenome = Neat<Value, Identity, Sum>;
+    type TestStore = store::Elite<TestGenome, TestPhenotype, f64>;
+    type TestWorld = World<TestGenome, Counter, TestStore>;
+
+    fn world(steps: u32, config: &Config<TestGenome, Counter, TestStore>) -> TestWorld {
+        let mut world = TestWorld::with_rng(Counter(steps), Random::seed_from_u64(0));
+        let (genome, brain, body) =
+            TestGenome::minimal(TestPhenotype, [(); 2], [()], world.rng_mut(), &config.genome);
+        let agent = Agent::new(genome, brain, body);
+        AgentStore::<_, Counter>::insert(world.store_mut(), agent, 0.0, &config.store);
+        world
+    }
+
+    fn converge_config() -> Config<TestGenome, Counter, TestStore> {
+        let settle = agent::Settle::Converge { epsilon: 0.0, max_ticks: 8 };
+        let brain = agent::Config { settle, ..Default::default() };
+        Config { brain, world_size: 4, ..Default::default() }
+    }
+
+    fn is_send<T: Send>() {}
+
+    #[test]
+    fn states_can_be_sent_to_other_threads() {
+        is_send::<agent::State<Value, Identity, Sum>>();
+        is_send::<State<TestGenome, Counter>>();
+        is_send::<TestWorld>();
+    }
+
+    #[test]
+    fn cycle_par_evaluates_all_agents() {
+        let config = Config {
+            genome: neat::Config { add_neuron_chance: 0.0, ..Default::default() },
+            store: store::elite::Config { capacity: 64 },
+            world_size: 50,
+            ..Default::default()
+        };
+        let mut world = world(3, &config);
+        let (_, score) = world.cycle_par(&config).expect("store should not be empty");
+        // NOTE: both sensors read `2` during the last step
+        assert_eq!(4.0, *score);
+        assert!(world.agents().is_empty());
+        assert_eq!(51, world.store().agents().len());
+    }
+
+    #[test]
+    fn convergence_settles_actions() {
+        let config = converge_config();
+        let mut world = world(3, &config).with_convergence();
+        let (_, score) = world.cycle_par(&config).expect("store should not be empty");
+        assert_eq!(4.0, *score);
+    }
+
+    #[test]
+    fn convergence_falls_back_to_max_ticks() {
+        let config = converge_config();
+        let mut world = world(3, &config);
+        world.initialize(&config);
+        assert_eq!(Some(()), world.step(&config));
+        let (_, score) = world.cycle_par(&config).expect("store should not be empty");
+        assert_eq!(4.0, *score);
+    }
+
+    #[test]
+    fn statistics_record_finished_cycles() {
+        let config = Config { world_size: 4, ..Default::default() };
+        let mut world = world(2, &config).with_statistics(|score| *score);
+        world.initialize(&config);
+        world.step(&config);
+        assert!(world.statistics().unwrap().history().is_empty());
+        world.step(&config);
+        world.statistics_mut().unwrap().record_species(1);
+        world.finalize(&config);
+        world.cycle_par(&config);
+        let history = world.statistics().unwrap().history();
+        assert_eq!(2, history.len());
+        assert_eq!((0, 4, Some(1)), (history[0].cycle, history[0].scored, history[0].species));
+        assert_eq!((1, 4, None), (history[1].cycle, history[1].scored, history[1].species));
+        let fitness = history[1].fitness.unwrap();
+        assert_eq!((2.0, 2.0), (fitness.min, fitness.max));
+        // NOTE: every brain keeps at least its three interface neurons
+        assert!(history[1].neurons.unwrap().min >= 3.0);
+    }
+
+    #[test]
+    fn same_seed_gives_same_run() {
+        let config = Config {
+            genome: neat::Config { add_neuron_chance: 0.5, ..Default::default() },
+            store: store::elite::Config { capacity: 8 },
+            world_size: 16,
+            ..Default::default()
+        };
+        let run = || {
+            let mut world = world(3, &config);
+            for _ in 0..3 {
+                world.cycle_par(&config);
+            }
+            // NOTE: brains don't implement `PartialEq` and genomes are unordered maps,
+            // so only the debug output of the phenotypes is compared
+            let agents = world.store().agents().iter();
+            agents.map(|(agent, _)| format!("{:?}", (agent.brain(), agent.body())))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    /// Counts all events, records the indices of all scored agents
+    /// and stops the cycle after `stop_after` steps.
+    #[derive(Debug, Default)]
+    struct Events {
+        created:    usize,
+        sensors:    usize,
+        actions:    usize,
+        scores:     Vec<usize>,
+        cycles:     usize,
+        stop_after: usize,
+    }
+    impl Observer<TestGenome, Counter, TestStore> for Events {
+        fn agent_created(
+            &mut self,
+            _index: usize,
+            _agent: &Agent<TestGenome, TestPhenotype>,
+            state: &u32,
+        ) {
+            assert_eq!(0, *state);
+            self.created += 1;
+        }
+
+        fn sensors_read(
+            &mut self,
+            _index: usize,
+            _agent: &Agent<TestGenome, TestPhenotype>,
+            outputs: &[Reading],
+        ) {
+            assert_eq!(2, outputs.len());
+            self.sensors += 1;
+        }
+
+        fn actions_performed(
+            &mut self,
+            _index: usize,
+            _agent: &Agent<TestGenome, TestPhenotype>,
+            _inputs: &[f64],
+            _state: &u32,
+        ) {
+            self.actions += 1;
+        }
+
+        fn score_assigned(
+            &mut self,
+            index: usize,
+            _agent: &Agent<TestGenome, TestPhenotype>,
+            _score: &f64,
+        ) {
+            self.scores.push(index);
+        }
+
+        fn cycle_end(&mut self, best: Option<StoreRef<TestGenome, Counter, f64>>) {
+            assert!(best.is_some());
+            self.cycles += 1;
+        }
+
+        fn should_stop(&mut self) -> bool {
+            self.stop_after = self.stop_after.saturating_sub(1);
+            self.stop_after == 0
+        }
+    }
+
+    #[test]
+    fn observer_receives_events() {
+        let config = Config { world_size: 4, ..Default::default() };
+        let events = Events { stop_after: 2, ..Default::default() };
+        let mut world = world(3, &config).with_observer(events);
+        world.initialize(&config);
+        assert_eq!(Some(()), world.step(&config));
+        assert_eq!(None, world.step(&config));
+        world.finalize(&config);
+        let counts = |e: &Events| (e.created, e.sensors, e.actions, e.scores.len(), e.cycles);
+        assert_eq!((4, 8, 8, 0, 1), counts(world.observer()));
+        world.cycle_par(&config);
+        // NOTE: the agents of the stopped cycle are finished by `cycle_par`
+        assert_eq!((8, 8, 8, 8, 2), counts(world.observer()));
+        assert_eq!((0..8).collect::<Vec<_>>(), world.observer().scores);
+    }
+
+    #[test]
+    fn step_sizes_buffers_per_agent() {
+        let config = Config { world_size: 4, ..Default::default() };
+        let mut world = world(2, &config);
+        world.initialize(&config);
+        assert_eq!(4, world.agents().len());
+        world.step(&config);
+        world.step(&config);
+        assert!(world.agents().is_empty());
+        assert_eq!(5, world.store().agents().len());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn checkpoint_resumes_step() {
+        let config = Config { world_size: 4, ..Default::default() };
+        let mut world = world(3, &config);
+        world.initialize(&config);
+        world.step(&config);
+        let mut buffer = Vec::new();
+        world.write_checkpoint(&mut buffer).unwrap();
+        let mut restored = TestWorld::read_checkpoint(buffer.as_slice()).unwrap();
+        assert_eq!(world.agents().len(), restored.agents().len());
+        assert_eq!(world.rng_mut(), restored.rng_mut());
+        for world in [&mut world, &mut restored] {
+            world.step(&config);
+            world.step(&config);
+            assert!(world.agents().is_empty());
+        }
+        let scores = |world: &TestWorld| {
+            world.store().agents().iter().map(|(_, score)| *score).collect::<Vec<_>>()
+        };
+        assert_eq!(scores(&world), scores(&restored));
+    }
 }
@@ -0,0 +1,73 @@
+use super::*;
+
+/// Receives events of a [`World`], e.g. for logging, visualisation or early stopping.
+///
+/// All methods do nothing by default, `()` is used when no observer is attached
+/// (see [`World::with_observer`]), so the calls are optimized away completely.
+///
+/// Indices refer to positions in [`World::agents`] at the time of the event.
+/// [`World::cycle_par`] evaluates agents on multiple threads and only reports
+/// agent creation, scores and the cycle end.
+#[expect(unused_variables)]
+pub trait Observer<G, C, S>
+where
+    // NOTE: `'static` bound is required by generic associated types at the moment
+    G: 'static + Genome,
+    C: Controller,
+    S: AgentStore<G, C>,
+{
+    /// Called for every agent created by [`AgentStore::populate`].
+    fn agent_created(
+        &mut self,
+        index: usize,
+        agent: &Agent<G, C::Phenotype>,
+        state: &C::State,
+    ) {
+    }
+
+    /// Called after [`Controller::read_sensors`] wrote `outputs`.
+    fn sensors_read(
+        &mut self,
+        index: usize,
+        agent: &Agent<G, C::Phenotype>,
+        outputs: &[C::SensorOutput],
+    ) {
+    }
+
+    /// Called after [`Controller::perform_actions`] applied `inputs`,
+    /// before the agent is removed when it received a score.
+    fn actions_performed(
+        &mut self,
+        index: usize,
+        agent: &Agent<G, C::Phenotype>,
+        inputs: &[C::ActionInput],
+        state: &C::State,
+    ) {
+    }
+
+    /// Called before `agent` is removed and inserted into the [`AgentStore`].
+    fn score_assigned(&mut self, index: usize, agent: &Agent<G, C::Phenotype>, score: &C::Score) {}
+
+    /// Called after an agent was created by [`Command::Spawn`].
+    fn spawned(&mut self, index: usize, agent: &Agent<G, C::Phenotype>, state: &C::State) {}
+
+    /// Called after an agent was removed by [`Command::Kill`].
+    fn killed(&mut self, index: usize, agent: &Agent<G, C::Phenotype>) {}
+
+    /// Called by [`World::finalize`] with the best agent of the store.
+    fn cycle_end(&mut self, best: Option<StoreRef<G, C, S::Score>>) {}
+
+    /// Checked once per [`World::step`] after all agents were stepped,
+    /// returning `true` ends the current cycle early.
+    fn should_stop(&mut self) -> bool {
+        false
+    }
+}
+
+impl<G, C, S> Observer<G, C, S> for ()
+where
+    G: 'static + Genome,
+    C: Controller,
+    S: AgentStore<G, C>,
+{
+}
@@ -0,0 +1,308 @@
+use std::{cmp::Ordering, fmt::Debug};
+
+use crate::agent::*;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum [`Agent::distance`] to the representative of a species to be counted as a member.
+    pub threshold:        f64,
+    /// Number of updates without improvement after which a species is removed.
+    pub stagnation_limit: u32,
+    /// Number of best species that are never removed due to stagnation.
+    pub protected:        usize,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self { threshold: 3.0, stagnation_limit: 15, protected: 2 }
+    }
+}
+
+/// Group of similar [`Agent`]s compared against a single representative.
+#[derive(Debug)]
+pub struct Species<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    id:             u32,
+    representative: Agent<G, P>,
+    members:        Vec<usize>,
+    best_fitness:   f64,
+    stagnation:     u32,
+    shared_fitness: f64,
+}
+impl<G, P> Species<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn new(id: u32, representative: Agent<G, P>) -> Self {
+        Self {
+            id,
+            representative,
+            members: Vec::new(),
+            best_fitness: f64::NEG_INFINITY,
+            stagnation: 0,
+            shared_fitness: 0.0,
+        }
+    }
+
+    /// Unique identifier, stays the same while the species exists.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn representative(&self) -> &Agent<G, P> {
+        &self.representative
+    }
+
+    /// Indices into the agents passed to the last call of [`Speciation::speciate`].
+    pub fn members(&self) -> &[usize] {
+        &self.members
+    }
+
+    /// Best raw fitness of any member seen so far.
+    pub fn best_fitness(&self) -> f64 {
+        self.best_fitness
+    }
+
+    /// Number of updates since [`Species::best_fitness`] last improved.
+    pub fn stagnation(&self) -> u32 {
+        self.stagnation
+    }
+
+    /// Sum of shared fitness of all members as of the last [`Speciation::update`].
+    pub fn shared_fitness(&self) -> f64 {
+        self.shared_fitness
+    }
+}
+
+/// Clusters a population into [`Species`] to protect new structures from competing
+/// against the whole population.
+///
+/// A typical generation looks like this:
+/// - [`Speciation::speciate`] to assign all agents to species
+/// - [`Speciation::update`] with the raw fitness to track stagnation and shared fitness
+/// - [`Speciation::cull`] to remove stagnant species
+/// - [`Speciation::offspring`] to decide how many children each species receives
+/// - optionally [`Speciation::share`] on a copy of the raw fitness
+///   to select parents by shared fitness
+#[derive(Debug)]
+pub struct Speciation<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    species: Vec<Species<G, P>>,
+    next_id: u32,
+}
+impl<G, P> Default for Speciation<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn default() -> Self {
+        Self { species: Vec::new(), next_id: 0 }
+    }
+}
+impl<G, P> Speciation<G, P>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn species(&self) -> &[Species<G, P>] {
+        &self.species
+    }
+
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.species.is_empty()
+    }
+
+    /// Returns the index of the species `agent` was assigned to.
+    pub fn species_of(&self, agent: usize) -> Option<usize> {
+        self.species.iter().position(|species| species.members.contains(&agent))
+    }
+
+    /// Assigns every agent to the first species with a representative closer than [`Config::threshold`].
+    /// Agents that don't match any species start a new one.
+    /// Species without members are removed and every remaining species uses its first member
+    /// as representative afterwards.
+    pub fn speciate(&mut self, agents: &[Agent<G, P>], config: &Config, genome: &G::Config) {
+        self.species.iter_mut().for_each(|species| species.members.clear());
+        for (index, agent) in agents.iter().enumerate() {
+            let found = self.species.iter_mut().find(|species| {
+                species.representative.distance(agent, genome) < config.threshold
+            });
+            if let Some(species) = found {
+                species.members.push(index);
+            } else {
+                let mut species = Species::new(self.next_id, agent.clone());
+                species.members.push(index);
+                self.species.push(species);
+                self.next_id += 1;
+            }
+        }
+        self.species.retain(|species| !species.members.is_empty());
+        for species in &mut self.species {
+            species.representative = agents[species.members[0]].clone();
+        }
+    }
+
+    /// Divides the fitness of every agent by the size of its species (explicit fitness sharing).
+    /// `fitness` has to be indexed the same way as the agents passed to [`Speciation::speciate`].
+    pub fn share(&self, fitness: &mut [f64]) {
+        for species in &self.species {
+            let size = species.members.len() as f64;
+            species.members.iter().for_each(|i| fitness[*i] /= size);
+        }
+    }
+
+    /// Updates best fitness and stagnation of every species using raw `fitness`
+    /// and stores the sum of shared fitness used by [`Speciation::offspring`].
+    pub fn update(&mut self, fitness: &[f64]) {
+        for species in &mut self.species {
+            let best =
+                species.members.iter().map(|i| fitness[*i]).fold(f64::NEG_INFINITY, f64::max);
+            if best > species.best_fitness {
+                species.best_fitness = best;
+                species.stagnation = 0;
+            } else {
+                species.stagnation += 1;
+            }
+            let size = species.members.len() as f64;
+            species.shared_fitness = species.members.iter().map(|i| fitness[*i] / size).sum();
+        }
+    }
+
+    /// Removes all species that reached [`Config::stagnation_limit`],
+    /// except the [`Config::protected`] species with the highest best fitness.
+    /// Returns the indices of all removed agents in descending order,
+    /// so they can be removed using [`Vec::swap_remove`].
+    pub fn cull(&mut self, config: &Config) -> Vec<usize> {
+        let mut ranking = (0..self.species.len()).collect::<Vec<_>>();
+        ranking.sort_by(|a, b| {
+            self.species[*b]
+                .best_fitness
+                .partial_cmp(&self.species[*a].best_fitness)
+                .unwrap_or(Ordering::Equal)
+        });
+        let mut protected = vec![false; self.species.len()];
+        ranking.into_iter().take(config.protected).for_each(|i| protected[i] = true);
+        let mut removed = Vec::new();
+        let mut protected = protected.into_iter();
+        self.species.retain(|species| {
+            let keep = protected.next().unwrap_or(true)
+                || species.stagnation < config.stagnation_limit;
+            if !keep {
+                removed.extend_from_slice(&species.members);
+            }
+            keep
+        });
+        removed.sort_unstable_by(|a, b| b.cmp(a));
+        removed
+    }
+
+    /// Distributes `count` children between all species proportional to their shared fitness.
+    /// Falls back to an even split when no species has positive shared fitness.
+    pub fn offspring(&self, count: usize) -> Vec<usize> {
+        if self.species.is_empty() {
+            return Vec::new();
+        }
+        let weights = self.species.iter().map(|species| species.shared_fitness.max(0.0));
+        let total = weights.clone().sum::<f64>();
+        let quotas = if total > 0.0 {
+            weights.map(|weight| weight / total * count as f64).collect::<Vec<_>>()
+        } else {
+            vec![count as f64 / self.species.len() as f64; self.species.len()]
+        };
+        let mut result = quotas.iter().map(|quota| quota.floor() as usize).collect::<Vec<_>>();
+        let mut remainders = (0..quotas.len()).collect::<Vec<_>>();
+        remainders.sort_by(|a, b| {
+            (quotas[*b] - quotas[*b].floor())
+                .partial_cmp(&(quotas[*a] - quotas[*a].floor()))
+                .unwrap_or(Ordering::Equal)
+        });
+        let missing = count - result.iter().sum::<usize>();
+        remainders.into_iter().cycle().take(missing).for_each(|i| result[i] += 1);
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use thin_vec::ThinVec;
+
+    use super::*;
+    use crate::test_util::*;
+
+    fn agents(points: &[f64]) -> Vec<Agent<Label, TestPhenotype>> {
+        points
+            .iter()
+            .map(|x| {
+                // SAFETY: empty interface is always ordered
+                let body =
+                    unsafe { Body::new_unchecked(ThinVec::new(), ThinVec::new(), TestPhenotype) };
+                Agent::new(Label(*x), Brain::new(), body)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn speciate_groups_close_agents() {
+        let config = Config { threshold: 1.0, ..Default::default() };
+        let mut speciation = Speciation::new();
+        speciation.speciate(&agents(&[0.0, 0.5, 5.0, 5.2, 10.0]), &config, &());
+        let members = speciation.species().iter().map(Species::members).collect::<Vec<_>>();
+        assert_eq!(vec![&[0, 1][..], &[2, 3], &[4]], members);
+        // NOTE: species keep their id while they have members
+        speciation.speciate(&agents(&[5.1, 0.2]), &config, &());
+        let ids = speciation.species().iter().map(Species::id).collect::<Vec<_>>();
+        assert_eq!(vec![0, 1], ids);
+        assert_eq!(Some(1), speciation.species_of(0));
+    }
+
+    #[test]
+    fn share_divides_by_species_size() {
+        let config = Config { threshold: 1.0, ..Default::default() };
+        let mut speciation = Speciation::new();
+        speciation.speciate(&agents(&[0.0, 0.5, 5.0]), &config, &());
+        let mut fitness = [2.0, 4.0, 3.0];
+        speciation.share(&mut fitness);
+        assert_eq!([1.0, 2.0, 3.0], fitness);
+    }
+
+    #[test]
+    fn cull_removes_stagnant_species() {
+        let config = Config { threshold: 1.0, stagnation_limit: 2, protected: 1 };
+        let mut speciation = Speciation::new();
+        let agents = agents(&[0.0, 5.0, 5.5, 10.0]);
+        speciation.speciate(&agents, &config, &());
+        speciation.update(&[1.0, 3.0, 3.0, 2.0]);
+        speciation.update(&[1.0, 3.0, 3.0, 2.0]);
+        assert_eq!(Vec::<usize>::new(), speciation.cull(&config));
+        speciation.update(&[1.0, 3.0, 3.0, 2.5]);
+        assert_eq!(vec![0], speciation.cull(&config));
+        assert_eq!(2, speciation.len());
+    }
+
+    #[test]
+    fn offspring_is_proportional_to_shared_fitness() {
+        let config = Config { threshold: 1.0, ..Default::default() };
+        let mut speciation = Speciation::new();
+        speciation.speciate(&agents(&[0.0, 5.0, 10.0]), &config, &());
+        speciation.update(&[1.0, 2.0, 1.0]);
+        let offspring = speciation.offspring(10);
+        assert_eq!(10, offspring.iter().sum::<usize>());
+        assert_eq!(5, offspring[1]);
+        speciation.update(&[0.0, 0.0, 0.0]);
+        assert_eq!(vec![1, 1, 1], speciation.offspring(3));
+    }
+}
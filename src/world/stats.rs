@@ -0,0 +1,255 @@
+use std::io::{self, Write};
+
+use super::*;
+
+/// Distribution of a set of values, percentiles are interpolated linearly between neighbours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    pub min:    f64,
+    pub p25:    f64,
+    pub median: f64,
+    pub p75:    f64,
+    pub max:    f64,
+    pub mean:   f64,
+}
+impl Summary {
+    /// Names of the columns written by [`Summary::write_csv`].
+    const COLUMNS: [&str; 6] = ["min", "p25", "median", "p75", "max", "mean"];
+
+    /// Summarizes all values that are not NaN, returns `None` when there are none.
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut values = values.into_iter().filter(|value| !value.is_nan()).collect::<Vec<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        Some(Self {
+            min: values[0],
+            p25: percentile(&values, 0.25),
+            median: percentile(&values, 0.5),
+            p75: percentile(&values, 0.75),
+            max: values[values.len() - 1],
+            mean,
+        })
+    }
+
+    /// Writes all fields as comma separated values, or empty columns for `None`.
+    fn write_csv(summary: Option<&Self>, mut writer: impl Write) -> io::Result<()> {
+        match summary {
+            Some(s) => {
+                write!(writer, "{},{},{},{},{},{}", s.min, s.p25, s.median, s.p75, s.max, s.mean)
+            },
+            None => write!(writer, ",,,,,"),
+        }
+    }
+}
+
+/// Returns the value at `fraction` of the sorted `values` (which can't be empty).
+fn percentile(values: &[f64], fraction: f64) -> f64 {
+    let position = fraction * (values.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    let weight = position - lower as f64;
+    values[lower] * (1.0 - weight) + values[upper] * weight
+}
+
+/// Statistics of a single cycle, see [`Statistics`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CycleStats {
+    /// Number of cycles recorded before this one.
+    pub cycle:       usize,
+    /// Number of agents that received a score during this cycle.
+    pub scored:      usize,
+    pub fitness:     Option<Summary>,
+    /// Neuron count of the brains of all scored agents.
+    pub neurons:     Option<Summary>,
+    /// Connection count of the brains of all scored agents.
+    pub connections: Option<Summary>,
+    /// Number of species, only known when set by [`Statistics::record_species`].
+    pub species:     Option<usize>,
+    /// Number of executed [`Command::Spawn`]s.
+    pub spawned:     usize,
+    /// Number of executed [`Command::Kill`]s.
+    pub killed:      usize,
+}
+impl CycleStats {
+    /// Writes the column names matching [`CycleStats::write_csv`], followed by a newline.
+    pub fn write_csv_header(mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "cycle,scored")?;
+        for group in ["fitness", "neurons", "connections"] {
+            for column in Summary::COLUMNS {
+                write!(writer, ",{group}_{column}")?;
+            }
+        }
+        writeln!(writer, ",species,spawned,killed")
+    }
+
+    /// Writes a single row of comma separated values, followed by a newline.
+    /// Values that are not known are left empty.
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{},{},", self.cycle, self.scored)?;
+        Summary::write_csv(self.fitness.as_ref(), &mut writer)?;
+        write!(writer, ",")?;
+        Summary::write_csv(self.neurons.as_ref(), &mut writer)?;
+        write!(writer, ",")?;
+        Summary::write_csv(self.connections.as_ref(), &mut writer)?;
+        write!(writer, ",")?;
+        if let Some(species) = self.species {
+            write!(writer, "{species}")?;
+        }
+        writeln!(writer, ",{},{}", self.spawned, self.killed)
+    }
+
+    /// Writes this as a single line of JSON, followed by a newline.
+    #[cfg(feature = "json")]
+    pub fn write_json(&self, mut writer: impl Write) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(&mut writer, self)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)
+    }
+}
+
+/// Records [`CycleStats`] of a [`World`], see [`World::with_statistics`].
+///
+/// Scores, brain sizes and commands are collected while agents are evaluated,
+/// a cycle is completed by [`World::finalize`] (this includes [`World::cycle_par`]).
+#[derive(Debug, Clone)]
+pub struct Statistics<S> {
+    fitness:     fn(&S) -> f64,
+    scores:      Vec<f64>,
+    neurons:     Vec<f64>,
+    connections: Vec<f64>,
+    species:     Option<usize>,
+    spawned:     usize,
+    killed:      usize,
+    history:     Vec<CycleStats>,
+}
+impl<S> Statistics<S> {
+    /// `fitness` maps the score of an agent to the value that is summarized.
+    pub fn new(fitness: fn(&S) -> f64) -> Self {
+        Self {
+            fitness,
+            scores: Vec::new(),
+            neurons: Vec::new(),
+            connections: Vec::new(),
+            species: None,
+            spawned: 0,
+            killed: 0,
+            history: Vec::new(),
+        }
+    }
+
+    pub(super) fn record_score<A, P>(&mut self, score: &S, brain: &Brain<A, P>)
+    where
+        A: Activator,
+        P: Propagator,
+    {
+        self.scores.push((self.fitness)(score));
+        self.neurons.push(brain.neurons().len() as f64);
+        self.connections.push(brain.connections().len() as f64);
+    }
+
+    pub(super) fn record_spawn(&mut self) {
+        self.spawned += 1;
+    }
+
+    pub(super) fn record_kill(&mut self) {
+        self.killed += 1;
+    }
+
+    /// Sets the number of species of the current cycle,
+    /// e.g. [`Speciation::len`](species::Speciation::len) after speciating the population.
+    pub fn record_species(&mut self, count: usize) {
+        self.species = Some(count);
+    }
+
+    /// Summarizes everything recorded since the last cycle and starts a new one.
+    pub(super) fn finish_cycle(&mut self) {
+        let stats = CycleStats {
+            cycle:       self.history.len(),
+            scored:      self.scores.len(),
+            fitness:     Summary::new(self.scores.drain(..)),
+            neurons:     Summary::new(self.neurons.drain(..)),
+            connections: Summary::new(self.connections.drain(..)),
+            species:     self.species.take(),
+            spawned:     std::mem::take(&mut self.spawned),
+            killed:      std::mem::take(&mut self.killed),
+        };
+        self.history.push(stats);
+    }
+
+    /// All completed cycles, oldest first.
+    pub fn history(&self) -> &[CycleStats] {
+        &self.history
+    }
+
+    /// The most recently completed cycle.
+    pub fn last(&self) -> Option<&CycleStats> {
+        self.history.last()
+    }
+
+    /// Writes all completed cycles as CSV including a header.
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        CycleStats::write_csv_header(&mut writer)?;
+        self.history.iter().try_for_each(|stats| stats.write_csv(&mut writer))
+    }
+
+    /// Writes all completed cycles as JSON lines.
+    #[cfg(feature = "json")]
+    pub fn write_json_lines(&self, mut writer: impl Write) -> Result<(), serde_json::Error> {
+        self.history.iter().try_for_each(|stats| stats.write_json(&mut writer))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::numeric::{Activation, Weighted};
+
+    #[test]
+    fn summary_interpolates_percentiles() {
+        let summary = Summary::new([4.0, f64::NAN, 1.0, 3.0, 2.0, 5.0]).unwrap();
+        let expected = Summary { min: 1.0, p25: 2.0, median: 3.0, p75: 4.0, max: 5.0, mean: 3.0 };
+        assert_eq!(expected, summary);
+        let summary = Summary::new([1.0, 2.0]).unwrap();
+        assert_eq!((1.25, 1.5, 1.75), (summary.p25, summary.median, summary.p75));
+        assert_eq!(None, Summary::new([f64::NAN]));
+    }
+
+    #[test]
+    fn csv_rows_match_header() {
+        let mut stats = Statistics::<f64>::new(|score| *score);
+        stats.record_score(&2.0, &Brain::<Activation, Weighted>::new());
+        stats.record_spawn();
+        stats.record_species(1);
+        stats.finish_cycle();
+        stats.record_kill();
+        stats.finish_cycle();
+        let mut buffer = Vec::new();
+        stats.write_csv(&mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert!(lines.iter().all(|line| line.split(',').count() == 23), "{csv}");
+        assert_eq!("0,1,2,2,2,2,2,2,0,0,0,0,0,0,0,0,0,0,0,0,1,1,0", lines[1]);
+        assert_eq!("1,0,,,,,,,,,,,,,,,,,,,,0,1", lines[2]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_lines_roundtrip() {
+        let mut stats = Statistics::<f64>::new(|score| *score);
+        stats.record_score(&1.5, &Brain::<Activation, Weighted>::new());
+        stats.finish_cycle();
+        stats.finish_cycle();
+        let mut buffer = Vec::new();
+        stats.write_json_lines(&mut buffer).unwrap();
+        let json = String::from_utf8(buffer).unwrap();
+        let parsed = json
+            .lines()
+            .map(|line| serde_json::from_str::<CycleStats>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(stats.history(), parsed);
+    }
+}
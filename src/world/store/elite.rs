@@ -0,0 +1,97 @@
+use std::fmt::Debug;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of agents kept in the store.
+    pub capacity: usize,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self { capacity: 16 }
+    }
+}
+
+/// Keeps the best [`Config::capacity`] agents across generations.
+///
+/// [`AgentStore::populate`] does not remove the elite,
+/// so they keep competing with their children in the next generation.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "Agent<G, P>: serde::Serialize, S: serde::Serialize",
+        deserialize = "Agent<G, P>: serde::Deserialize<'de>, S: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Elite<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    /// Sorted by score in descending order.
+    agents: Vec<(Agent<G, P>, S)>,
+}
+impl<G, P, S> Default for Elite<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn default() -> Self {
+        Self { agents: Vec::new() }
+    }
+}
+impl<G, P, S> Elite<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    /// Returns all agents sorted by score in descending order.
+    pub fn agents(&self) -> &[(Agent<G, P>, S)] {
+        &self.agents
+    }
+}
+
+impl<G, C, S> AgentStore<G, C> for Elite<G, C::Phenotype, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+    S: Debug + PartialOrd + From<C::Score>,
+{
+    type Config = Config;
+    type Score = S;
+
+    /// Inserts `agent` when it is better than the worst agent or the store is not full yet.
+    fn insert(&mut self, agent: Agent<G, C::Phenotype>, score: Self::Score, config: &Self::Config) {
+        let index = self.agents.partition_point(|(_, other)| compare(other, &score).is_ge());
+        if index < config.capacity {
+            self.agents.insert(index, (agent, score));
+            self.agents.truncate(config.capacity);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    fn best(&self, _config: &Self::Config) -> Option<StoreRef<G, C, Self::Score>> {
+        self.agents.first().map(|(agent, score)| (agent, score))
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = (Agent<G, C::Phenotype>, Self::Score)> {
+        self.agents.drain(..)
+    }
+
+    /// Uses all stored agents as parents, fittest first.
+    fn populate(
+        &mut self,
+        count: usize,
+        rng: &mut Random,
+        config: (&Self::Config, &G::Config),
+    ) -> impl Iterator<Item = Agent<G, C::Phenotype>> {
+        let parents = self.agents.iter().map(|(agent, _)| agent.clone()).collect::<Vec<_>>();
+        Agent::populate(parents, count, rng, config.1)
+    }
+}
@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+
+use super::*;
+
+pub mod elite;
+pub mod pareto;
+pub mod selection;
+pub mod tournament;
+
+pub use elite::Elite;
+pub use pareto::Pareto;
+pub use selection::Selection;
+pub use tournament::Tournament;
+
+/// Compares scores ranking values that are incomparable even to themselves (e.g. `NaN`)
+/// below every other score, so sorting and searching by score stays consistent.
+fn compare<S: PartialOrd>(a: &S, b: &S) -> Ordering {
+    let unordered = |score: &S| score.partial_cmp(score).is_none();
+    match (unordered(a), unordered(b)) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fmt::Debug, marker::PhantomData};
+
+    use rand::SeedableRng;
+    use thin_vec::ThinVec;
+
+    use super::*;
+    use crate::test_util::*;
+
+    #[derive(Debug)]
+    struct TestController<S>(PhantomData<S>);
+    impl<S: Debug> Controller for TestController<S> {
+        type ActionInput = f64;
+        type Config = ();
+        type ParentIter = std::iter::Empty<usize>;
+        type Phenotype = TestPhenotype;
+        type Score = S;
+        type SensorOutput = f64;
+        type SpawnHelper = ();
+        type State = ();
+
+        fn initial_state(
+            &self,
+            _phenotype: &Self::Phenotype,
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) {
+        }
+
+        fn create_state(
+            &self,
+            _phenotype: &Self::Phenotype,
+            _init: Self::SpawnHelper,
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) {
+        }
+
+        fn read_sensors<'s>(
+            &self,
+            _state: &Self::State,
+            _sensors: impl IntoIterator<Item = &'s ()>,
+            _outputs: &mut [Self::SensorOutput],
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) {
+        }
+
+        fn perform_actions<'a>(
+            &self,
+            _state: &mut Self::State,
+            _actions: impl IntoIterator<Item = &'a ()>,
+            _inputs: &[Self::ActionInput],
+            _rng: &mut Random,
+            _config: &Self::Config,
+        ) -> Option<Self::Score> {
+            None
+        }
+    }
+    type Scalar = TestController<f64>;
+    type Objectives = TestController<[f64; 2]>;
+
+    fn agent(label: u32) -> Agent<Label, TestPhenotype> {
+        // SAFETY: empty interface is always ordered
+        let body = unsafe { Body::new_unchecked(ThinVec::new(), ThinVec::new(), TestPhenotype) };
+        Agent::new(Label(label.into()), Brain::new(), body)
+    }
+
+    fn fill<C, S>(store: &mut S, scores: impl IntoIterator<Item = S::Score>, config: &S::Config)
+    where
+        C: Controller<Phenotype = TestPhenotype>,
+        S: AgentStore<Label, C>,
+    {
+        for (label, score) in scores.into_iter().enumerate() {
+            store.insert(agent(label as u32), score, config);
+        }
+    }
+
+    fn labels<C, S>(store: &mut S, count: usize, config: &S::Config) -> Vec<u32>
+    where
+        C: Controller<Phenotype = TestPhenotype>,
+        S: AgentStore<Label, C>,
+    {
+        let mut rng = Random::seed_from_u64(0);
+        store
+            .populate(count, &mut rng, (config, &()))
+            .map(|agent| agent.genome().0 as u32)
+            .collect()
+    }
+
+    #[test]
+    fn elite_keeps_best_agents() {
+        let config = elite::Config { capacity: 2 };
+        let mut store = Elite::default();
+        fill::<Scalar, _>(&mut store, [1.0, 4.0, 2.0, 3.0], &config);
+        assert_eq!(2, AgentStore::<_, Scalar>::len(&store));
+        let (agent, score) = AgentStore::<_, Scalar>::best(&store, &config).unwrap();
+        assert_eq!((Label(1.0), 4.0), (*agent.genome(), *score));
+        assert_eq!(vec![1, 3, 1], labels::<Scalar, _>(&mut store, 3, &config));
+        // NOTE: the elite survives `populate`
+        assert_eq!(2, AgentStore::<_, Scalar>::len(&store));
+
+        let mut store = Elite::default();
+        fill::<Scalar, _>(&mut store, [f64::NAN, 1.0, f64::NAN, 0.5], &config);
+        let scores = store.agents().iter().map(|(agent, score)| (agent.genome().0, *score));
+        assert_eq!(vec![(1.0, 1.0), (3.0, 0.5)], scores.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn tournament_prefers_better_agents() {
+        let config = tournament::Config { size: 16 };
+        let mut store = Tournament::default();
+        fill::<Scalar, _>(&mut store, [1.0, 8.0, 2.0, 3.0], &config);
+        let (agent, _) = AgentStore::<_, Scalar>::best(&store, &config).unwrap();
+        assert_eq!(Label(1.0), *agent.genome());
+        let labels = labels::<Scalar, _>(&mut store, 100, &config);
+        assert_eq!(100, labels.len());
+        assert!(labels.iter().filter(|label| **label == 1).count() > 50);
+        assert!(AgentStore::<_, Scalar>::is_empty(&store));
+    }
+
+    #[test]
+    fn selection_never_picks_zero_weight() {
+        for weighting in [selection::Weighting::Fitness, selection::Weighting::Rank] {
+            let config = selection::Config { weighting };
+            let mut store = Selection::default();
+            fill::<Scalar, _>(&mut store, [-1.0, 3.0, 1.0], &config);
+            let labels = labels::<Scalar, _>(&mut store, 100, &config);
+            assert_eq!(100, labels.len());
+            let worst = labels.iter().filter(|label| **label == 0).count();
+            match weighting {
+                selection::Weighting::Fitness => assert_eq!(0, worst),
+                selection::Weighting::Rank => assert!(worst < 50),
+            }
+        }
+    }
+
+    #[test]
+    fn selection_survives_non_finite_scores() {
+        for weighting in [selection::Weighting::Fitness, selection::Weighting::Rank] {
+            let config = selection::Config { weighting };
+            let mut store = Selection::default();
+            fill::<Scalar, _>(&mut store, [f64::NAN, f64::INFINITY, 2.0, f64::MAX], &config);
+            let picked = labels::<Scalar, _>(&mut store, 100, &config);
+            assert_eq!(100, picked.len());
+            match weighting {
+                selection::Weighting::Fitness => assert!(picked.iter().all(|label| *label >= 2)),
+                selection::Weighting::Rank => assert!(picked.iter().any(|label| *label == 1)),
+            }
+            // NOTE: the sum of fitness weights overflows
+            fill::<Scalar, _>(&mut store, [f64::MAX, f64::MIN], &config);
+            assert_eq!(100, labels::<Scalar, _>(&mut store, 100, &config).len());
+        }
+    }
+
+    #[test]
+    fn pareto_selects_from_front() {
+        let config = pareto::Config { tournament_size: 32, objective: 1 };
+        let mut store = Pareto::default();
+        fill::<Objectives, _>(
+            &mut store,
+            [[1.0, 4.0], [4.0, 1.0], [2.0, 2.0], [1.0, 1.0], [0.0, 0.0]],
+            &config,
+        );
+        let front = store.front().map(|(agent, _)| agent.genome().0 as u32).collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 2], front);
+        let (agent, _) = AgentStore::<_, Objectives>::best(&store, &config).unwrap();
+        assert_eq!(Label(0.0), *agent.genome());
+        let labels = labels::<Objectives, _>(&mut store, 100, &config);
+        assert!(labels.iter().all(|label| *label < 3));
+    }
+}
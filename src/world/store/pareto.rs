@@ -0,0 +1,192 @@
+use std::fmt::Debug;
+
+use rand::Rng;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Number of randomly chosen agents competing for each parent slot.
+    pub tournament_size: usize,
+    /// Index of the objective used to choose [`AgentStore::best`] from the Pareto front.
+    pub objective:       usize,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self { tournament_size: 2, objective: 0 }
+    }
+}
+
+/// Returns `true` when `a` is at least as good as `b` in every objective and better in one.
+fn dominates(a: &[f64], b: &[f64]) -> bool {
+    let mut better = false;
+    for (a, b) in a.iter().zip(b) {
+        if a < b {
+            return false;
+        }
+        better |= a > b;
+    }
+    better
+}
+
+/// Collects a whole generation scored by multiple objectives (higher is better)
+/// and selects parents by non-dominated sorting with crowding distance (NSGA-II).
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "Agent<G, P>: serde::Serialize, S: serde::Serialize",
+        deserialize = "Agent<G, P>: serde::Deserialize<'de>, S: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Pareto<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    agents: Vec<(Agent<G, P>, S)>,
+}
+impl<G, P, S> Default for Pareto<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn default() -> Self {
+        Self { agents: Vec::new() }
+    }
+}
+impl<G, P, S> Pareto<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+    S: AsRef<[f64]>,
+{
+    fn is_dominated(&self, score: &S) -> bool {
+        self.agents.iter().any(|(_, other)| dominates(other.as_ref(), score.as_ref()))
+    }
+
+    /// Returns all agents that are not dominated by any other agent.
+    pub fn front(&self) -> impl Iterator<Item = &(Agent<G, P>, S)> {
+        self.agents.iter().filter(|(_, score)| !self.is_dominated(score))
+    }
+
+    /// Returns the front index of every agent, where front `0` is the Pareto front.
+    fn ranks(&self) -> Vec<usize> {
+        let len = self.agents.len();
+        let mut dominated_by = vec![0usize; len];
+        let mut dominating = vec![Vec::new(); len];
+        for (i, (_, a)) in self.agents.iter().enumerate() {
+            for (j, (_, b)) in self.agents.iter().enumerate() {
+                if dominates(a.as_ref(), b.as_ref()) {
+                    dominating[i].push(j);
+                    dominated_by[j] += 1;
+                }
+            }
+        }
+        let mut ranks = vec![0; len];
+        let mut front = (0..len).filter(|i| dominated_by[*i] == 0).collect::<Vec<_>>();
+        let mut rank = 0;
+        while !front.is_empty() {
+            let mut next = Vec::new();
+            for i in front {
+                ranks[i] = rank;
+                for j in &dominating[i] {
+                    dominated_by[*j] -= 1;
+                    if dominated_by[*j] == 0 {
+                        next.push(*j);
+                    }
+                }
+            }
+            front = next;
+            rank += 1;
+        }
+        ranks
+    }
+
+    /// Returns the crowding distance of every agent within its front.
+    fn crowding(&self, ranks: &[usize]) -> Vec<f64> {
+        let len = self.agents.len();
+        let objectives = self.agents.first().map_or(0, |(_, score)| score.as_ref().len());
+        let mut distance = vec![0.0; len];
+        for rank in 0..=ranks.iter().copied().max().unwrap_or(0) {
+            let mut front = (0..len).filter(|i| ranks[*i] == rank).collect::<Vec<_>>();
+            for objective in 0..objectives {
+                let value = |i: usize| self.agents[i].1.as_ref()[objective];
+                front.sort_by(|a, b| compare(&value(*a), &value(*b)));
+                let (Some(&first), Some(&last)) = (front.first(), front.last()) else {
+                    continue;
+                };
+                distance[first] = f64::INFINITY;
+                distance[last] = f64::INFINITY;
+                let range = value(last) - value(first);
+                if range > 0.0 {
+                    for window in front.windows(3) {
+                        distance[window[1]] += (value(window[2]) - value(window[0])) / range;
+                    }
+                }
+            }
+        }
+        distance
+    }
+}
+
+impl<G, C, S> AgentStore<G, C> for Pareto<G, C::Phenotype, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+    S: Debug + AsRef<[f64]> + From<C::Score>,
+{
+    type Config = Config;
+    type Score = S;
+
+    fn insert(&mut self, agent: Agent<G, C::Phenotype>, score: Self::Score, _config: &Self::Config) {
+        self.agents.push((agent, score));
+    }
+
+    fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// Returns the agent of the Pareto front that is best in [`Config::objective`].
+    fn best(&self, config: &Self::Config) -> Option<StoreRef<G, C, Self::Score>> {
+        let value = |score: &S| score.as_ref().get(config.objective).copied();
+        self.front()
+            .max_by(|a, b| compare(&value(&a.1), &value(&b.1)))
+            .map(|(agent, score)| (agent, score))
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = (Agent<G, C::Phenotype>, Self::Score)> {
+        self.agents.drain(..)
+    }
+
+    /// Selects one parent per child by tournaments comparing front index first
+    /// and crowding distance second, then removes all agents.
+    fn populate(
+        &mut self,
+        count: usize,
+        rng: &mut Random,
+        config: (&Self::Config, &G::Config),
+    ) -> impl Iterator<Item = Agent<G, C::Phenotype>> {
+        let ranks = self.ranks();
+        let crowding = self.crowding(&ranks);
+        let better = |a: usize, b: usize| {
+            ranks[b].cmp(&ranks[a]).then_with(|| compare(&crowding[a], &crowding[b]))
+        };
+        let parents = if self.agents.is_empty() {
+            Vec::new()
+        } else {
+            (0..count)
+                .map(|_| {
+                    let winner = (0..config.0.tournament_size.max(1))
+                        .map(|_| rng.random_range(0..self.agents.len()))
+                        .max_by(|a, b| better(*a, *b))
+                        .expect("tournament size is at least one");
+                    self.agents[winner].0.clone()
+                })
+                .collect()
+        };
+        self.agents.clear();
+        Agent::populate(parents, count, rng, config.1)
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::Debug;
+
+use rand::distr::{Distribution, weighted::WeightedIndex};
+
+use super::*;
+
+/// How the selection probability of an agent is calculated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Weighting {
+    /// Proportional to the score shifted to be non-negative (roulette wheel selection).
+    /// Agents with a score that is not finite are never selected.
+    #[default]
+    Fitness,
+    /// Proportional to the position when sorted by score, the worst agent has weight `1`.
+    /// Agents with a `NaN` score are ranked worst.
+    Rank,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub weighting: Weighting,
+}
+
+/// Collects a whole generation and selects parents with a probability based on their score.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "Agent<G, P>: serde::Serialize, S: serde::Serialize",
+        deserialize = "Agent<G, P>: serde::Deserialize<'de>, S: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Selection<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    agents: Vec<(Agent<G, P>, S)>,
+}
+impl<G, P, S> Default for Selection<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn default() -> Self {
+        Self { agents: Vec::new() }
+    }
+}
+impl<G, P, S> Selection<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+    S: Copy + PartialOrd + Into<f64>,
+{
+    /// Returns the selection weight of every agent.
+    /// Falls back to uniform weights when no agent has a positive weight
+    /// or the sum of all weights is not finite.
+    fn weights(&self, weighting: Weighting) -> Vec<f64> {
+        match weighting {
+            Weighting::Fitness => {
+                let scores = self.agents.iter().map(|(_, score)| (*score).into());
+                let min = scores.clone().filter(|score| score.is_finite()).fold(0.0, f64::min);
+                let weights = scores
+                    .map(|score| if score.is_finite() { score - min } else { 0.0 })
+                    .collect::<Vec<_>>();
+                if weights.iter().any(|weight| *weight > 0.0)
+                    && weights.iter().sum::<f64>().is_finite()
+                {
+                    weights
+                } else {
+                    vec![1.0; weights.len()]
+                }
+            },
+            Weighting::Rank => {
+                let score = |i: usize| {
+                    let score: f64 = self.agents[i].1.into();
+                    if score.is_nan() { f64::NEG_INFINITY } else { score }
+                };
+                let mut order = (0..self.agents.len()).collect::<Vec<_>>();
+                order.sort_by(|a, b| score(*a).total_cmp(&score(*b)));
+                let mut weights = vec![0.0; order.len()];
+                order.into_iter().enumerate().for_each(|(rank, i)| weights[i] = (rank + 1) as f64);
+                weights
+            },
+        }
+    }
+}
+
+impl<G, C, S> AgentStore<G, C> for Selection<G, C::Phenotype, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+    S: Debug + Copy + PartialOrd + Into<f64> + From<C::Score>,
+{
+    type Config = Config;
+    type Score = S;
+
+    fn insert(&mut self, agent: Agent<G, C::Phenotype>, score: Self::Score, _config: &Self::Config) {
+        self.agents.push((agent, score));
+    }
+
+    fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    fn best(&self, _config: &Self::Config) -> Option<StoreRef<G, C, Self::Score>> {
+        self.agents.iter().max_by(|a, b| compare(&a.1, &b.1)).map(|(agent, score)| (agent, score))
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = (Agent<G, C::Phenotype>, Self::Score)> {
+        self.agents.drain(..)
+    }
+
+    /// Selects one parent per child and removes all agents afterwards.
+    fn populate(
+        &mut self,
+        count: usize,
+        rng: &mut Random,
+        config: (&Self::Config, &G::Config),
+    ) -> impl Iterator<Item = Agent<G, C::Phenotype>> {
+        let parents = match WeightedIndex::new(self.weights(config.0.weighting)) {
+            Ok(distribution) => distribution
+                .sample_iter(&mut *rng)
+                .take(count)
+                .map(|i| self.agents[i].0.clone())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        self.agents.clear();
+        Agent::populate(parents, count, rng, config.1)
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt::Debug;
+
+use rand::Rng;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Number of randomly chosen agents competing for each parent slot.
+    pub size: usize,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self { size: 3 }
+    }
+}
+
+/// Collects a whole generation and selects parents by tournament selection.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "Agent<G, P>: serde::Serialize, S: serde::Serialize",
+        deserialize = "Agent<G, P>: serde::Deserialize<'de>, S: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Tournament<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    agents: Vec<(Agent<G, P>, S)>,
+}
+impl<G, P, S> Default for Tournament<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+{
+    fn default() -> Self {
+        Self { agents: Vec::new() }
+    }
+}
+impl<G, P, S> Tournament<G, P, S>
+where
+    G: Genome,
+    P: Phenotype,
+    S: PartialOrd,
+{
+    /// Returns the index of the winner of a tournament between `size` random agents.
+    fn select(&self, size: usize, rng: &mut impl Rng) -> usize {
+        (0..size.max(1))
+            .map(|_| rng.random_range(0..self.agents.len()))
+            .max_by(|a, b| compare(&self.agents[*a].1, &self.agents[*b].1))
+            .expect("tournament size is at least one")
+    }
+}
+
+impl<G, C, S> AgentStore<G, C> for Tournament<G, C::Phenotype, S>
+where
+    G: 'static + Genome,
+    C: Controller,
+    S: Debug + PartialOrd + From<C::Score>,
+{
+    type Config = Config;
+    type Score = S;
+
+    fn insert(&mut self, agent: Agent<G, C::Phenotype>, score: Self::Score, _config: &Self::Config) {
+        self.agents.push((agent, score));
+    }
+
+    fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    fn best(&self, _config: &Self::Config) -> Option<StoreRef<G, C, Self::Score>> {
+        self.agents.iter().max_by(|a, b| compare(&a.1, &b.1)).map(|(agent, score)| (agent, score))
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = (Agent<G, C::Phenotype>, Self::Score)> {
+        self.agents.drain(..)
+    }
+
+    /// Selects one parent per child and removes all agents afterwards.
+    fn populate(
+        &mut self,
+        count: usize,
+        rng: &mut Random,
+        config: (&Self::Config, &G::Config),
+    ) -> impl Iterator<Item = Agent<G, C::Phenotype>> {
+        let parents = if self.agents.is_empty() {
+            Vec::new()
+        } else {
+            (0..count)
+                .map(|_| self.agents[self.select(config.0.size, rng)].0.clone())
+                .collect()
+        };
+        self.agents.clear();
+        Agent::populate(parents, count, rng, config.1)
+    }
+}